@@ -0,0 +1,10 @@
+use cosmwasm_schema::write_api;
+use cw_bidding_factory::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecMsg,
+        query: QueryMsg,
+    }
+}
@@ -0,0 +1,123 @@
+use cosmwasm_std::{DepsMut, Reply, Response, StdResult};
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::state::{Config, CONFIG};
+
+const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+pub const INSTANTIATE_AUCTION_REPLY_ID: u64 = 1;
+
+pub fn instantiate(deps: DepsMut, bidding_code_id: u64) -> StdResult<Response> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    CONFIG.save(deps.storage, &Config { bidding_code_id })?;
+
+    Ok(Response::new())
+}
+
+pub fn reply(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+    match reply.id {
+        INSTANTIATE_AUCTION_REPLY_ID => exec::auction_instantiated(deps, reply),
+        id => Err(ContractError::UnknownReplyId { id }),
+    }
+}
+
+pub mod query {
+    use cosmwasm_std::{Deps, Order, StdResult};
+    use cw_storage_plus::Bound;
+
+    use crate::msg::{AuctionsResp, ConfigResp};
+    use crate::state::{AUCTIONS, CONFIG};
+
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
+
+    pub fn config(deps: Deps) -> StdResult<ConfigResp> {
+        let config = CONFIG.load(deps.storage)?;
+        Ok(ConfigResp { bidding_code_id: config.bidding_code_id })
+    }
+
+    pub fn auctions_by_creator(
+        deps: Deps,
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResp> {
+        let creator = deps.api.addr_validate(&creator)?;
+        let start_after = match start_after {
+            Some(i) => Some(deps.api.addr_validate(&i)?),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let auctions = AUCTIONS
+            .prefix(&creator)
+            .keys(deps.storage, start_after.as_ref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(AuctionsResp { auctions })
+    }
+}
+
+pub mod exec {
+    use cosmwasm_std::{to_binary, DepsMut, Empty, Env, MessageInfo, Reply, Response, SubMsg, WasmMsg};
+    use cw_bidding_platform::msg::InstantiateMsg;
+    use cw_utils::parse_reply_instantiate_data;
+
+    use crate::error::ContractError;
+    use crate::state::{AUCTIONS, AUCTION_COUNT, CONFIG, PENDING_CREATOR};
+
+    use super::INSTANTIATE_AUCTION_REPLY_ID;
+
+    pub fn create_auction(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        mut msg: InstantiateMsg,
+    ) -> Result<Response, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+
+        // The factory instantiates the auction, so it would become its owner by default
+        if msg.owner.is_none() {
+            msg.owner = Some(info.sender.to_string());
+        }
+
+        let auction_count = AUCTION_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+        AUCTION_COUNT.save(deps.storage, &auction_count)?;
+        PENDING_CREATOR.save(deps.storage, &info.sender)?;
+
+        let wasm_msg = WasmMsg::Instantiate {
+            admin: Some(info.sender.to_string()),
+            code_id: config.bidding_code_id,
+            msg: to_binary(&msg)?,
+            funds: vec![],
+            label: format!("Bidding contract {}", auction_count),
+        };
+
+        let resp = Response::new()
+            .add_submessage(SubMsg::reply_on_success(wasm_msg, INSTANTIATE_AUCTION_REPLY_ID))
+            .add_attribute("action", "create_auction")
+            .add_attribute("creator", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn auction_instantiated(deps: DepsMut, reply: Reply) -> Result<Response, ContractError> {
+        let res = parse_reply_instantiate_data(reply)?;
+        let auction = deps.api.addr_validate(&res.contract_address)?;
+
+        let creator = PENDING_CREATOR.load(deps.storage)?;
+        PENDING_CREATOR.remove(deps.storage);
+        AUCTIONS.save(deps.storage, (&creator, &auction), &Empty {})?;
+
+        let resp = Response::new()
+            .add_attribute("action", "auction_instantiated")
+            .add_attribute("creator", creator.as_str())
+            .add_attribute("auction", auction.as_str());
+
+        Ok(resp)
+    }
+}
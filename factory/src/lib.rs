@@ -0,0 +1,56 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdResult};
+use error::ContractError;
+use msg::InstantiateMsg;
+
+mod contract;
+pub mod error;
+pub mod msg;
+#[cfg(any(test, feature = "tests"))]
+pub mod multitest;
+mod state;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    contract::instantiate(deps, msg.bidding_code_id)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: msg::ExecMsg,
+) -> Result<Response, ContractError> {
+    use contract::exec;
+    use msg::ExecMsg::*;
+
+    match msg {
+        CreateAuction(msg) => exec::create_auction(deps, env, info, msg),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, reply: Reply) -> Result<Response, ContractError> {
+    contract::reply(deps, reply)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+    use contract::query;
+    use msg::QueryMsg::*;
+
+    match msg {
+        Config {} => to_binary(&query::config(deps)?),
+        AuctionsByCreator { creator, start_after, limit } => {
+            to_binary(&query::auctions_by_creator(deps, creator, start_after, limit)?)
+        },
+    }
+}
@@ -0,0 +1,38 @@
+use cosmwasm_std::Addr;
+use cosmwasm_schema::{cw_serde, QueryResponses};
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    /// Code id of the cw-bidding-platform contract to instantiate
+    pub bidding_code_id: u64,
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(ConfigResp)]
+    Config {},
+
+    #[returns(AuctionsResp)]
+    AuctionsByCreator {
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub enum ExecMsg {
+    /// Instantiates a new bidding contract, owned by the sender unless `owner` is set
+    CreateAuction(cw_bidding_platform::msg::InstantiateMsg),
+}
+
+#[cw_serde]
+pub struct ConfigResp {
+    pub bidding_code_id: u64,
+}
+
+#[cw_serde]
+pub struct AuctionsResp {
+    pub auctions: Vec<Addr>,
+}
@@ -0,0 +1,77 @@
+use cosmwasm_std::{Addr, StdResult};
+use cw_bidding_platform::msg::InstantiateMsg as BiddingInstantiateMsg;
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+use crate::error::ContractError;
+use crate::msg::{AuctionsResp, ConfigResp, ExecMsg, InstantiateMsg, QueryMsg};
+use crate::{execute, instantiate, query, reply};
+
+#[derive(Debug)]
+pub struct FactoryContract(Addr);
+
+impl FactoryContract {
+    pub fn addr(&self) -> &Addr {
+        &self.0
+    }
+
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query).with_reply(reply);
+        app.store_code(Box::new(contract))
+    }
+
+    #[track_caller]
+    pub fn instantiate(
+        app: &mut App,
+        code_id: u64,
+        sender: &Addr,
+        label: &str,
+        bidding_code_id: u64,
+    ) -> StdResult<Self> {
+        app.instantiate_contract(
+            code_id,
+            sender.clone(),
+            &InstantiateMsg { bidding_code_id },
+            &[],
+            label,
+            None,
+        )
+        .map(FactoryContract)
+        .map_err(|err| err.downcast().unwrap())
+    }
+
+    #[track_caller]
+    pub fn create_auction(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        msg: BiddingInstantiateMsg,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::CreateAuction(msg), &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn query_config(&self, app: &App) -> StdResult<ConfigResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Config {})
+    }
+
+    #[track_caller]
+    pub fn query_auctions_by_creator(
+        &self,
+        app: &App,
+        creator: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<AuctionsResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::AuctionsByCreator { creator, start_after, limit })
+    }
+}
+
+impl From<FactoryContract> for Addr {
+    fn from(contract: FactoryContract) -> Self {
+        contract.0
+    }
+}
@@ -0,0 +1,53 @@
+use cosmwasm_std::{Addr, Uint128, coins};
+use cw_bidding_platform::multitest::contract::{BiddingContract, AUCTION_ID, BID_DENOM};
+use cw_multi_test::App;
+
+use crate::msg::ConfigResp;
+
+use super::contract::FactoryContract;
+
+#[test]
+fn create_auction() {
+    let owner1 = Addr::unchecked("owner");
+    let owner2 = Addr::unchecked("seller");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let bidding_code_id = BiddingContract::store_code(&mut app);
+    let code_id = FactoryContract::store_code(&mut app);
+    let factory = FactoryContract::instantiate(&mut app, code_id, &owner1, "Bidding factory", bidding_code_id).unwrap();
+
+    assert_eq!(factory.query_config(&app).unwrap(), ConfigResp { bidding_code_id });
+
+    factory.create_auction(&mut app, &owner1, BiddingContract::default_instantiate_msg()).unwrap();
+    factory.create_auction(&mut app, &owner1, BiddingContract::default_instantiate_msg()).unwrap();
+    factory.create_auction(&mut app, &owner2, BiddingContract::default_instantiate_msg()).unwrap();
+
+    let resp = factory.query_auctions_by_creator(&app, owner1.to_string(), None, None).unwrap();
+    assert_eq!(resp.auctions.len(), 2);
+
+    let resp = factory.query_auctions_by_creator(&app, owner1.to_string(), None, Some(1)).unwrap();
+    assert_eq!(resp.auctions.len(), 1);
+    let resp = factory
+        .query_auctions_by_creator(&app, owner1.to_string(), Some(resp.auctions[0].to_string()), None)
+        .unwrap();
+    assert_eq!(resp.auctions.len(), 1);
+
+    let resp = factory.query_auctions_by_creator(&app, owner2.to_string(), None, None).unwrap();
+    assert_eq!(resp.auctions.len(), 1);
+
+    // The creator owns the new auction, not the factory
+    let auction = BiddingContract::from(resp.auctions[0].clone());
+    assert_eq!(auction.query_ownership(&app, AUCTION_ID).unwrap().owner, owner2);
+
+    auction.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    auction.close(&mut app, &owner2, AUCTION_ID).unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&owner2, BID_DENOM).unwrap().amount,
+        Uint128::from(100u128),
+    );
+}
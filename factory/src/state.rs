@@ -0,0 +1,14 @@
+use cosmwasm_std::{Addr, Empty};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Config {
+    pub bidding_code_id: u64,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+// Creator of the auction being instantiated, until the reply comes back
+pub const PENDING_CREATOR: Item<Addr> = Item::new("pending_creator");
+pub const AUCTION_COUNT: Item<u64> = Item::new("auction_count");
+pub const AUCTIONS: Map<(&Addr, &Addr), Empty> = Map::new("auctions");
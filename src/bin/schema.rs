@@ -1,10 +1,11 @@
 use cosmwasm_schema::write_api;
-use cw_bidding_platform::msg::{ExecMsg, InstantiateMsg, QueryMsg};
+use cw_bidding_platform::msg::{ExecMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         execute: ExecMsg,
         query: QueryMsg,
+        migrate: MigrateMsg,
     }
 }
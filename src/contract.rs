@@ -1,236 +1,2088 @@
-use cosmwasm_std::{DepsMut, Response, StdResult, Decimal, MessageInfo};
-use cw2::{set_contract_version};
-use crate::state::{State, STATE};
+use std::str::FromStr;
+
+use cosmwasm_std::{Addr, BlockInfo, Binary, Decimal, DepsMut, Env, Order, Response, MessageInfo, StdResult, Storage, Timestamp, Uint128};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+use sha2::{Digest, Sha256};
+use crate::error::ContractError;
+use crate::msg::{
+    AuctionStatus, AuctionType, CommissionPolicy, DecayUnit, FeeBase, PriceDecay, InstantiateMsg, MigrateMsg, MinIncrement, Nft, ReservePrice,
+};
+use crate::state::{FeeShare, State, AUCTION_COUNT, auctions, bids};
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub fn instantiate(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    commodity_uri: String,
-    bid_comission: Decimal,
-    owner: Option<String>,
-) -> StdResult<Response> {
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let auction_id = create_auction(deps, &env, info.sender, msg, None)?;
+
+    let resp = Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("auction_id", auction_id.to_string());
+
+    Ok(resp)
+}
+
+/// Validates the auction parameters and stores the auction under a new id
+pub fn create_auction(
+    deps: DepsMut,
+    env: &Env,
+    sender: Addr,
+    msg: InstantiateMsg,
+    nft: Option<Nft>,
+) -> Result<u64, ContractError> {
+    if msg.bid_denom.is_empty() && msg.cw20_addr.is_none() {
+        return Err(ContractError::InvalidBidDenom {});
+    }
+
+    // End time must leave room for bids
+    if let Some(end_time) = msg.end_time {
+        let opens_at = msg.start_time.unwrap_or(env.block.time).max(env.block.time);
+        if end_time <= opens_at {
+            return Err(ContractError::InvalidBidWindow {});
+        }
+    }
+
+    if let Some(extension) = &msg.extension {
+        if msg.end_time.is_none() || extension.duration == 0 {
+            return Err(ContractError::InvalidBidExtension {});
+        }
+    }
+
+    let auction_type = msg.auction_type.unwrap_or_default();
+    let winner_only = matches!(msg.commission_policy, None | Some(CommissionPolicy::WinnerOnly));
+    if let Some(sealed) = auction_type.sealed() {
+        let invalid_terms = sealed.reveal_period == 0 || sealed.penalty > Decimal::one();
+        // Second-price winners pay commission on a price only known at close
+        let second_price = matches!(auction_type, AuctionType::Vickrey(_));
+        if msg.end_time.is_none() || msg.extension.is_some() || invalid_terms || (second_price && !winner_only) {
+            return Err(ContractError::InvalidSealedAuction {});
+        }
+    }
+
+    if let Some(buy_now_price) = msg.buy_now_price {
+        let below_reserve = matches!(msg.reserve_price, Some(ReservePrice::Public(i)) if buy_now_price < i);
+        if auction_type != AuctionType::English || buy_now_price.is_zero() || below_reserve {
+            return Err(ContractError::InvalidBuyNowPrice {});
+        }
+    }
+
+    // Units are paid for at close, so unit bids are never extended, stepped up or charged commission
+    if let AuctionType::MultiUnit(lot) = &auction_type {
+        let unsupported = msg.extension.is_some() || msg.min_increment.is_some() || !winner_only;
+        if lot.units == 0 || nft.is_some() || unsupported {
+            return Err(ContractError::InvalidMultiUnitAuction {});
+        }
+    }
+    let commission_policy = match auction_type {
+        AuctionType::Vickrey(_) | AuctionType::MultiUnit(_) => CommissionPolicy::WinnerOnly,
+        _ => msg.commission_policy.unwrap_or_default(),
+    };
+
+    // Dutch prices decay from the start time, which defaults to now
+    let mut start_time = msg.start_time;
+    if let AuctionType::Dutch(dutch) = &auction_type {
+        let (decays, unit) = match &dutch.decay {
+            PriceDecay::Linear { duration, unit } => (*duration > 0, unit),
+            PriceDecay::Stepwise { amount, interval, unit } => (!amount.is_zero() && *interval > 0, unit),
+        };
+
+        // Block decay counts from the creation block, the auction cannot open later
+        let starts_later = matches!(msg.start_time, Some(i) if i > env.block.time);
+        let block_decay_later = *unit == DecayUnit::Blocks && starts_later;
+
+        // The first bid meeting the price wins, so the floor price cannot undercut the reserve
+        let below_reserve = match &msg.reserve_price {
+            Some(ReservePrice::Public(i)) => dutch.floor_price < *i,
+            Some(ReservePrice::Hidden(_)) => true,
+            None => false,
+        };
+
+        if dutch.floor_price > dutch.start_price || !decays || block_decay_later || below_reserve {
+            return Err(ContractError::InvalidDutchAuction {});
+        }
+        start_time = start_time.or(Some(env.block.time));
+    }
+
+    let auction_owner = match msg.owner {
+        Some(i) => deps.api.addr_validate(&i)?,
+        None => sender,
+    };
+
+    let cw20_addr = match msg.cw20_addr {
+        Some(i) => Some(deps.api.addr_validate(&i)?),
+        None => None,
+    };
+
+    let mut fee_recipients = vec![];
+    let mut total_share = Decimal::zero();
+    for i in msg.fee_recipients.unwrap_or_default() {
+        total_share += i.share;
+        if total_share > Decimal::one() {
+            return Err(ContractError::InvalidFeeShares {});
+        }
+        fee_recipients.push(FeeShare { address: deps.api.addr_validate(&i.address)?, share: i.share });
+    }
+
+    let auction_id = AUCTION_COUNT.may_load(deps.storage)?.unwrap_or_default() + 1;
+    AUCTION_COUNT.save(deps.storage, &auction_id)?;
+
+    auctions().save(
+        deps.storage,
+        auction_id,
+        &State {
+            commodity_uri: msg.commodity_uri,
+            nft,
+            owner: auction_owner,
+            bid_comission: msg.bid_comission,
+            bid_denom: msg.bid_denom,
+            cw20_addr,
+            start_time,
+            end_time: msg.end_time,
+            extension: msg.extension,
+            reserve_price: msg.reserve_price,
+            min_increment: msg.min_increment,
+            buy_now_price: msg.buy_now_price,
+            commission_policy,
+            fee_recipients,
+            fee_base: msg.fee_base.unwrap_or_default(),
+            claim_grace_period: msg.claim_grace_period,
+            cancel_requires_no_bids: msg.cancel_requires_no_bids,
+            auction_type,
+            start_height: env.block.height,
+            status: AuctionStatus::Active,
+        },
+    )?;
+
+    Ok(auction_id)
+}
+
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract { contract: stored.contract });
+    }
+
+    let from: Version = stored.version.parse()?;
+    let to: Version = CONTRACT_VERSION.parse()?;
+    if from > to {
+        return Err(ContractError::InvalidMigrationVersion { from: stored.version, to: CONTRACT_VERSION.to_string() });
+    }
+
+    // Older layouts are moved straight to the current one
+    if from < Version::new(0, 3, 0) {
+        migration::from_v0_1(deps.storage)?;
+    } else if from < Version::new(0, 6, 0) {
+        if from < Version::new(0, 5, 0) {
+            if from < Version::new(0, 4, 0) {
+                migration::from_v0_3(deps.storage)?;
+            }
+            migration::from_v0_4(deps.storage)?;
+        }
+        migration::from_v0_5(deps.storage)?;
+    }
+
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    let contract_owner = match owner {
-        Some(i) => deps.api.addr_validate(&i)?,
-        None => info.sender,
-    };
+    let resp = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION);
+
+    Ok(resp)
+}
+
+/// Actions taking an auction from one status to the next
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Bid,
+    BidUnits,
+    CommitBid,
+    RevealBid,
+    Retract,
+    RefundAll,
+    RevealReserve,
+    /// Close with a winning bid
+    Sell,
+    /// Close with a highest bid below the reserve price
+    CloseBelowReserve,
+    /// Close without bids
+    CloseUnsold,
+    Cancel,
+    Claim,
+}
+
+impl Action {
+    fn name(&self) -> &'static str {
+        match self {
+            Action::Bid => "bid",
+            Action::BidUnits => "bid_units",
+            Action::CommitBid => "commit_bid",
+            Action::RevealBid => "reveal_bid",
+            Action::Retract => "retract",
+            Action::RefundAll => "refund_all",
+            Action::RevealReserve => "reveal_reserve",
+            Action::Sell | Action::CloseBelowReserve | Action::CloseUnsold => "close",
+            Action::Cancel => "cancel",
+            Action::Claim => "claim",
+        }
+    }
+}
+
+/// Status of an auction at the given time
+pub fn status(state: &State, now: Timestamp) -> AuctionStatus {
+    if state.status.is_closed() {
+        return state.status.clone();
+    }
+
+    let reveal_period = state.auction_type.sealed().map(|i| i.reveal_period).unwrap_or_default();
+
+    match (state.start_time, state.end_time) {
+        (Some(start_time), _) if now < start_time => AuctionStatus::Scheduled,
+        (_, Some(end_time)) if now >= end_time.plus_seconds(reveal_period) => AuctionStatus::Ended,
+        (_, Some(end_time)) if now >= end_time => AuctionStatus::Revealing,
+        _ => AuctionStatus::Active,
+    }
+}
+
+/// Applies an action to the auction, rejecting it unless allowed in the current status
+pub fn transition(state: &mut State, now: Timestamp, action: Action) -> Result<(), ContractError> {
+    use AuctionStatus::*;
+
+    // Each auction type takes its own kind of bids
+    let supported = match (&state.auction_type, action) {
+        (AuctionType::English | AuctionType::Dutch(_), Action::Bid) => true,
+        (AuctionType::Sealed(_) | AuctionType::Vickrey(_), Action::CommitBid | Action::RevealBid) => true,
+        (AuctionType::MultiUnit(_), Action::BidUnits) => true,
+        (_, Action::Bid | Action::CommitBid | Action::RevealBid | Action::BidUnits) => false,
+        _ => true,
+    };
+    if !supported {
+        return Err(ContractError::UnsupportedAction { action: action.name().to_string() });
+    }
+
+    let sealed = state.auction_type.sealed().is_some();
+
+    let current = status(state, now);
+
+    // Sealed auctions only close once every bid had the chance to be revealed
+    let closable = current == Ended || (current == Active && !sealed);
+
+    let next = match (&current, action) {
+        (Active, Action::Bid | Action::CommitBid | Action::BidUnits) => Active,
+        (Revealing, Action::RevealBid) => Active,
+        (Scheduled | Active | Revealing | Ended, Action::RevealReserve) => Active,
+        (_, Action::Sell) if closable => AwaitingSettlement,
+        (_, Action::CloseBelowReserve) if closable => ReserveNotMet,
+        (_, Action::CloseUnsold) if closable => Unsold,
+        (Scheduled | Active, Action::Cancel) => Cancelled,
+        (AwaitingSettlement, Action::Claim) => Settled,
+        // Bids can be retracted from any closed auction, the status stays as is
+        (AwaitingSettlement | Settled | ReserveNotMet | Unsold | Cancelled, Action::Retract | Action::RefundAll) => {
+            current.clone()
+        },
+        _ => return Err(ContractError::InvalidTransition { action: action.name().to_string(), status: current }),
+    };
+
+    state.status = next;
+    Ok(())
+}
+
+/// Lowest total bid that beats the current highest bid
+pub fn min_next_bid(min_increment: &Option<MinIncrement>, highest_bid: Uint128) -> Uint128 {
+    let increment = match min_increment {
+        Some(MinIncrement::Absolute(i)) => *i,
+        Some(MinIncrement::Percentage(i)) => highest_bid * *i,
+        None => Uint128::zero(),
+    };
+
+    // Bids must always be strictly higher
+    highest_bid + increment.max(Uint128::one())
+}
+
+/// Price of a Dutch auction at the given block, `None` for other auction types
+pub fn current_price(state: &State, block: &BlockInfo) -> Option<Uint128> {
+    let dutch = match &state.auction_type {
+        AuctionType::Dutch(i) => i,
+        _ => return None,
+    };
+
+    let elapsed = |unit: &DecayUnit| match unit {
+        DecayUnit::Seconds => {
+            let start_time = state.start_time.unwrap_or(block.time);
+            block.time.seconds().saturating_sub(start_time.seconds())
+        },
+        DecayUnit::Blocks => block.height.saturating_sub(state.start_height),
+    };
+
+    let decay = match &dutch.decay {
+        PriceDecay::Linear { duration, unit } => {
+            (dutch.start_price - dutch.floor_price).multiply_ratio(elapsed(unit).min(*duration), *duration)
+        },
+        PriceDecay::Stepwise { amount, interval, unit } => {
+            amount.saturating_mul(Uint128::from(elapsed(unit) / interval))
+        },
+    };
+
+    Some(dutch.start_price.saturating_sub(decay).max(dutch.floor_price))
+}
+
+/// Commission due on a bid amount, rounded up
+pub fn commission(amount: Uint128, bid_comission: Decimal) -> Result<Uint128, ContractError> {
+    let commission = Decimal::from_atomics(amount, 0)?
+        .checked_mul(bid_comission)?
+        .ceil();
+    Ok(Uint128::from_str(&commission.to_string())?)
+}
+
+/// Splits commission and sale proceeds between the fee recipients and the owner.
+/// Each fee recipient's part is rounded down, the owner gets the rest.
+pub fn payouts(state: &State, commission: Uint128, proceeds: Uint128) -> Vec<(Addr, Uint128)> {
+    let base = match state.fee_base {
+        FeeBase::Commission => commission,
+        FeeBase::Proceeds => proceeds,
+        FeeBase::All => commission + proceeds,
+    };
+
+    let mut owner_amount = commission + proceeds;
+    let mut payouts = vec![];
+    for i in &state.fee_recipients {
+        let amount = base * i.share;
+        if !amount.is_zero() {
+            owner_amount -= amount;
+            payouts.push((i.address.clone(), amount));
+        }
+    }
+    if !owner_amount.is_zero() {
+        payouts.push((state.owner.clone(), owner_amount));
+    }
+
+    payouts
+}
+
+/// Highest non-zero bids of an auction, best first
+pub fn top_bids(storage: &dyn Storage, auction_id: u64, limit: usize) -> StdResult<Vec<(Addr, Uint128)>> {
+    bids()
+        .idx
+        .amount
+        .sub_prefix(auction_id)
+        .range(storage, None, None, Order::Descending)
+        .map(|item| item.map(|((_, address), bid)| (address, bid.amount)))
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .take(limit)
+        .collect()
+}
+
+/// Commitment for a sealed bid: sha256 of the bidder address, the amount and the salt
+pub fn bid_commitment(bidder: &Addr, amount: Uint128, salt: &str) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(bidder.as_bytes());
+    hasher.update(amount.to_string().as_bytes());
+    hasher.update(salt.as_bytes());
+    Binary::from(hasher.finalize().to_vec())
+}
+
+/// Commitment for a hidden reserve price: sha256 of the amount followed by the salt
+pub fn reserve_commitment(amount: Uint128, salt: &str) -> Binary {
+    let mut hasher = Sha256::new();
+    hasher.update(amount.to_string().as_bytes());
+    hasher.update(salt.as_bytes());
+    Binary::from(hasher.finalize().to_vec())
+}
+
+pub mod migration {
+    use cosmwasm_std::{Addr, Decimal, Order, StdResult, Storage, Timestamp, Uint128};
+    use cw_storage_plus::{Item, Map};
+    use serde::{Deserialize, Serialize};
+
+    use crate::msg::{AuctionStatus, AuctionType, BidExtension, CommissionPolicy, FeeBase, MinIncrement, ReservePrice, SettlementStatus};
+    use crate::state::{Bid, State, Winner, AUCTION_COUNT, PENDING_OWNER, SETTLEMENT, WINNER, auctions, bids};
+
+    pub const BIDS_V0_1: Map<&Addr, Uint128> = Map::new("bids");
+    pub const WINNER_V0_1: Item<Winner> = Item::new("winner");
+
+    // Single auction layout up to version 0.2.0, 0.1.0 states lacking the optional fields
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct StateV0_2 {
+        pub commodity_uri: String,
+        pub owner: Addr,
+        pub bid_comission: Decimal,
+        #[serde(default)]
+        pub bid_denom: String,
+        pub start_time: Option<Timestamp>,
+        pub end_time: Option<Timestamp>,
+        pub extension: Option<BidExtension>,
+        pub reserve_price: Option<ReservePrice>,
+        pub min_increment: Option<MinIncrement>,
+        pub is_closed: bool,
+        #[serde(default)]
+        pub reserve_not_met: bool,
+    }
+
+    pub const STATE_V0_2: Item<StateV0_2> = Item::new("state");
+    pub const PENDING_OWNER_V0_2: Item<Addr> = Item::new("pending_owner");
+
+    // Bids were stored as plain amounts, without the amount index, up to version 0.3.0
+    pub const BIDS_V0_3: Map<(u64, &Addr), Uint128> = Map::new("auction_bids");
+
+    // Auction status flags, replaced by `AuctionStatus` in version 0.5.0
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    pub struct StatusV0_4 {
+        pub is_closed: bool,
+        #[serde(default)]
+        pub is_cancelled: bool,
+        #[serde(default)]
+        pub reserve_not_met: bool,
+    }
+
+    pub const STATUS_V0_4: Map<u64, StatusV0_4> = Map::new("auctions");
+
+    // Auctions were stored without the `ListAuctions` indexes up to version 0.5.0
+    pub const AUCTIONS_V0_5: Map<u64, State> = Map::new("auctions");
+
+    // The single auction of older versions becomes the first one of the registry
+    pub const AUCTION_ID_V0_1: u64 = 1;
+
+    // Bids were only accepted in "ATOM" before the denom became configurable
+    pub const BID_DENOM_V0_1: &str = "ATOM";
+
+    // Moves the single auction layout, up to version 0.2.0, into the registry
+    pub fn from_v0_1(storage: &mut dyn Storage) -> StdResult<()> {
+        let old = STATE_V0_2.load(storage)?;
+        STATE_V0_2.remove(storage);
+
+        // Closed auctions had no settlement step, the winner was paid out right away
+        let status = match (old.is_closed, old.reserve_not_met, WINNER_V0_1.may_load(storage)?.is_some()) {
+            (false, _, _) => AuctionStatus::Active,
+            (true, true, _) => AuctionStatus::ReserveNotMet,
+            (true, false, true) => AuctionStatus::Settled,
+            (true, false, false) => AuctionStatus::Unsold,
+        };
+
+        let bid_denom = if old.bid_denom.is_empty() { BID_DENOM_V0_1.to_string() } else { old.bid_denom };
+
+        AUCTION_COUNT.save(storage, &AUCTION_ID_V0_1)?;
+        auctions().save(
+            storage,
+            AUCTION_ID_V0_1,
+            &State {
+                commodity_uri: old.commodity_uri,
+                nft: None,
+                owner: old.owner,
+                bid_comission: old.bid_comission,
+                bid_denom,
+                cw20_addr: None,
+                start_time: old.start_time,
+                end_time: old.end_time,
+                extension: old.extension,
+                reserve_price: old.reserve_price,
+                min_increment: old.min_increment,
+                buy_now_price: None,
+                commission_policy: CommissionPolicy::OnEveryBid,
+                fee_recipients: vec![],
+                fee_base: FeeBase::Commission,
+                claim_grace_period: None,
+                cancel_requires_no_bids: false,
+                auction_type: AuctionType::English,
+                start_height: 0,
+                status,
+            },
+        )?;
+
+        if let Some(winner) = WINNER_V0_1.may_load(storage)? {
+            WINNER_V0_1.remove(storage);
+            WINNER.save(storage, AUCTION_ID_V0_1, &winner)?;
+        }
+
+        if let Some(pending_owner) = PENDING_OWNER_V0_2.may_load(storage)? {
+            PENDING_OWNER_V0_2.remove(storage);
+            PENDING_OWNER.save(storage, AUCTION_ID_V0_1, &pending_owner)?;
+        }
+
+        let old = BIDS_V0_1
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (address, amount) in old {
+            BIDS_V0_1.remove(storage, &address);
+            let bid = Bid { amount, ..Bid::empty(AUCTION_ID_V0_1) };
+            bids().save(storage, (AUCTION_ID_V0_1, &address), &bid)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn from_v0_3(storage: &mut dyn Storage) -> StdResult<()> {
+        let old = BIDS_V0_3
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for ((auction_id, address), amount) in old {
+            // Removed first, as saving would try to read the old value as a `Bid`
+            BIDS_V0_3.remove(storage, (auction_id, &address));
+            bids().save(storage, (auction_id, &address), &Bid { amount, ..Bid::empty(auction_id) })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn from_v0_4(storage: &mut dyn Storage) -> StdResult<()> {
+        let old = STATUS_V0_4
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (auction_id, flags) in old {
+            let settlement = SETTLEMENT.may_load(storage, auction_id)?.map(|i| i.status);
+            let status = if !flags.is_closed {
+                AuctionStatus::Active
+            } else if flags.is_cancelled {
+                AuctionStatus::Cancelled
+            } else if flags.reserve_not_met {
+                AuctionStatus::ReserveNotMet
+            } else {
+                match settlement {
+                    Some(SettlementStatus::Pending) => AuctionStatus::AwaitingSettlement,
+                    Some(SettlementStatus::Delivered) => AuctionStatus::Settled,
+                    Some(SettlementStatus::Cancelled) => AuctionStatus::Unsold,
+                    // Settlements were not tracked before, the winner was paid out at close
+                    None if WINNER.has(storage, auction_id) => AuctionStatus::Settled,
+                    None => AuctionStatus::Unsold,
+                }
+            };
+
+            // Loads with the default status, the old flags are dropped on save
+            let mut state = AUCTIONS_V0_5.load(storage, auction_id)?;
+            state.status = status;
+            AUCTIONS_V0_5.save(storage, auction_id, &state)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn from_v0_5(storage: &mut dyn Storage) -> StdResult<()> {
+        let old = AUCTIONS_V0_5
+            .range(storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        for (auction_id, state) in old {
+            // There are no index entries yet, so nothing to remove
+            auctions().replace(storage, auction_id, Some(&state), None)?;
+        }
+
+        Ok(())
+    }
+}
+
+pub mod query {
+    use cosmwasm_std::{Deps, Env, Order, StdResult, Uint128};
+    use cw_storage_plus::Bound;
+
+    use crate::msg::{
+        AuctionStatus, TotalUserBidResp, HighestBidResp, StatusResp, WinnerResp, ConfigResp, EndTimeResp, MinNextBidResp,
+        OwnershipResp, AuctionResp, ListAuctionsResp, BidOrder, BidResp, AllBidsResp, TopBidsResp,
+        BidRecordResp, BidHistoryResp, FeeConfigResp, FeeShareResp, SettlementResp, CurrentPriceResp, AllocationResp,
+    };
+    use crate::state::{WINNER, PENDING_OWNER, SETTLEMENT, ALLOCATIONS, auctions, bids, bid_log, unit_bids};
+
+    pub fn total_user_bid(deps: Deps, auction_id: u64, address: String) -> StdResult<TotalUserBidResp> {
+        let address = deps.api.addr_validate(&address)?;
+        let amount = bids()
+            .may_load(deps.storage, (auction_id, &address))?
+            .map(|i| i.amount)
+            .unwrap_or_default();
+        Ok(TotalUserBidResp { amount })
+    }
+
+    // we show this even if bid is closed
+    pub fn highest_bid(deps: Deps, auction_id: u64) -> StdResult<Option<HighestBidResp>> {
+        let winner = WINNER.may_load(deps.storage, auction_id)?;
+        match winner {
+            Some(i) => Ok(Some(HighestBidResp { address: i.address, amount: i.amount })),
+            None => Ok(None),
+        }
+    }
+
+    pub fn status(deps: Deps, env: Env, auction_id: u64) -> StdResult<StatusResp> {
+        let state = auctions().load(deps.storage, auction_id)?;
+        Ok(StatusResp { status: super::status(&state, env.block.time) })
+    }
+
+    pub fn winner(deps: Deps, auction_id: u64) -> StdResult<Option<WinnerResp>> {
+        let state = auctions().load(deps.storage, auction_id)?;
+        let reserve_met = match state.status {
+            AuctionStatus::AwaitingSettlement | AuctionStatus::Settled => true,
+            AuctionStatus::ReserveNotMet => false,
+            _ => return Ok(None),
+        };
+
+        let winner = WINNER.may_load(deps.storage, auction_id)?;
+        let settlement = SETTLEMENT.may_load(deps.storage, auction_id)?;
+        match winner {
+            Some(i) => Ok(Some(WinnerResp {
+                address: i.address,
+                // Nothing is paid without a sale
+                price: if reserve_met { settlement.and_then(|s| s.price).unwrap_or(i.amount) } else { Uint128::zero() },
+                amount: i.amount,
+                reserve_met,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    pub fn config(deps: Deps, auction_id: u64) -> StdResult<ConfigResp> {
+        let state = auctions().load(deps.storage, auction_id)?;
+        Ok(ConfigResp {
+            commodity_uri: state.commodity_uri,
+            nft: state.nft,
+            owner: state.owner,
+            bid_comission: state.bid_comission,
+            bid_denom: state.bid_denom,
+            cw20_addr: state.cw20_addr,
+            start_time: state.start_time,
+            end_time: state.end_time,
+            extension: state.extension,
+            reserve_price: state.reserve_price,
+            min_increment: state.min_increment,
+            buy_now_price: state.buy_now_price,
+            commission_policy: state.commission_policy,
+            auction_type: state.auction_type,
+        })
+    }
+
+    pub fn fee_config(deps: Deps, auction_id: u64) -> StdResult<FeeConfigResp> {
+        let state = auctions().load(deps.storage, auction_id)?;
+        let recipients = state.fee_recipients
+            .into_iter()
+            .map(|i| FeeShareResp { address: i.address, share: i.share })
+            .collect();
+        Ok(FeeConfigResp { recipients, base: state.fee_base })
+    }
+
+    // current end time, including extensions from late bids
+    pub fn end_time(deps: Deps, auction_id: u64) -> StdResult<EndTimeResp> {
+        let end_time = auctions().load(deps.storage, auction_id)?.end_time;
+        Ok(EndTimeResp { end_time })
+    }
+
+    pub fn min_next_bid(deps: Deps, auction_id: u64) -> StdResult<MinNextBidResp> {
+        let min_increment = auctions().load(deps.storage, auction_id)?.min_increment;
+        let highest_bid = WINNER
+            .may_load(deps.storage, auction_id)?
+            .map(|i| i.amount)
+            .unwrap_or_default();
+        let amount = super::min_next_bid(&min_increment, highest_bid);
+        Ok(MinNextBidResp { amount })
+    }
+
+    pub fn current_price(deps: Deps, env: Env, auction_id: u64) -> StdResult<Option<CurrentPriceResp>> {
+        let state = auctions().load(deps.storage, auction_id)?;
+        if state.status.is_closed() {
+            return Ok(None);
+        }
+
+        let price = super::current_price(&state, &env.block);
+        Ok(price.map(|price| CurrentPriceResp { price }))
+    }
+
+    pub fn allocation(deps: Deps, auction_id: u64, address: String) -> StdResult<Option<AllocationResp>> {
+        let address = deps.api.addr_validate(&address)?;
+        let unit_bid = match unit_bids().may_load(deps.storage, (auction_id, &address))? {
+            Some(i) => i,
+            None => return Ok(None),
+        };
+
+        let allocated = ALLOCATIONS
+            .may_load(deps.storage, (auction_id, &address))?
+            .unwrap_or_default();
+        let clearing_price = SETTLEMENT
+            .may_load(deps.storage, auction_id)?
+            .and_then(|i| i.price);
+        let refundable = bids()
+            .may_load(deps.storage, (auction_id, &address))?
+            .map(|i| i.held())
+            .unwrap_or_default();
+
+        Ok(Some(AllocationResp {
+            quantity: unit_bid.quantity,
+            unit_price: unit_bid.unit_price,
+            allocated,
+            clearing_price,
+            refundable,
+        }))
+    }
+
+    pub fn ownership(deps: Deps, auction_id: u64) -> StdResult<OwnershipResp> {
+        let owner = auctions().load(deps.storage, auction_id)?.owner;
+        let pending_owner = PENDING_OWNER.may_load(deps.storage, auction_id)?;
+        Ok(OwnershipResp { owner, pending_owner })
+    }
+
+    pub fn settlement(deps: Deps, auction_id: u64) -> StdResult<Option<SettlementResp>> {
+        let settlement = SETTLEMENT.may_load(deps.storage, auction_id)?;
+        Ok(settlement.map(|i| SettlementResp { status: i.status, closed_at: i.closed_at }))
+    }
+
+    const DEFAULT_LIMIT: u32 = 10;
+    const MAX_LIMIT: u32 = 30;
+
+    pub fn all_bids(
+        deps: Deps,
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<BidOrder>,
+    ) -> StdResult<AllBidsResp> {
+        let start_after = match start_after {
+            Some(i) => Some(deps.api.addr_validate(&i)?),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let bids = match order_by.unwrap_or(BidOrder::Bidder) {
+            BidOrder::Bidder => bids()
+                .prefix(auction_id)
+                .range(deps.storage, start_after.as_ref().map(Bound::exclusive), None, Order::Ascending)
+                .take(limit)
+                .map(|item| item.map(|(address, bid)| BidResp { address, amount: bid.amount }))
+                .collect::<StdResult<Vec<_>>>()?,
+            BidOrder::Amount => {
+                // Continue right after the last bidder of the previous page
+                let start_after = match start_after {
+                    Some(i) => bids()
+                        .may_load(deps.storage, (auction_id, &i))?
+                        .map(|bid| (bid.amount.u128(), i)),
+                    None => None,
+                };
+                let max = start_after
+                    .as_ref()
+                    .map(|(amount, address)| Bound::exclusive((*amount, (auction_id, address))));
+
+                bids()
+                    .idx
+                    .amount
+                    .sub_prefix(auction_id)
+                    .range(deps.storage, None, max, Order::Descending)
+                    .take(limit)
+                    .map(|item| item.map(|((_, address), bid)| BidResp { address, amount: bid.amount }))
+                    .collect::<StdResult<Vec<_>>>()?
+            },
+        };
+
+        Ok(AllBidsResp { bids })
+    }
+
+    pub fn top_bids(deps: Deps, auction_id: u64, limit: Option<u32>) -> StdResult<TopBidsResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let bids = super::top_bids(deps.storage, auction_id, limit)?
+            .into_iter()
+            .map(|(address, amount)| BidResp { address, amount })
+            .collect();
+        Ok(TopBidsResp { bids })
+    }
+
+    pub fn bid_history(
+        deps: Deps,
+        auction_id: u64,
+        bidder: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BidHistoryResp> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+        let records = match bidder {
+            Some(i) => {
+                let bidder = deps.api.addr_validate(&i)?;
+                bid_log()
+                    .idx
+                    .bidder
+                    .prefix((auction_id, bidder))
+                    .range(
+                        deps.storage,
+                        start_after.map(|seq| Bound::exclusive((auction_id, seq))),
+                        None,
+                        Order::Ascending,
+                    )
+                    .take(limit)
+                    .map(|item| item.map(|((_, seq), record)| (seq, record)))
+                    .collect::<StdResult<Vec<_>>>()?
+            },
+            None => bid_log()
+                .prefix(auction_id)
+                .range(deps.storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<_>>>()?,
+        };
+
+        let bids = records
+            .into_iter()
+            .map(|(seq, record)| BidRecordResp {
+                seq,
+                bidder: record.bidder,
+                amount: record.amount,
+                commission: record.commission,
+                net_amount: record.net_amount,
+                height: record.height,
+                time: record.time,
+            })
+            .collect();
+
+        Ok(BidHistoryResp { bids })
+    }
+
+    pub fn list_auctions(
+        deps: Deps,
+        env: Env,
+        owner: Option<String>,
+        closed: Option<bool>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ListAuctionsResp> {
+        let owner = match owner {
+            Some(i) => Some(deps.api.addr_validate(&i)?),
+            None => None,
+        };
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+        let start = start_after.map(Bound::exclusive);
+        let closed = closed.map(u8::from);
+
+        // Each filter has its own index, so pages never scan past the auctions they list
+        let idx = auctions().idx;
+        let auctions = match (owner, closed) {
+            (Some(owner), Some(closed)) => idx.owner_closed.prefix((owner, closed)),
+            (Some(owner), None) => idx.owner.prefix(owner),
+            (None, Some(closed)) => idx.closed.prefix(closed),
+            (None, None) => auctions().prefix(()),
+        };
+
+        let auctions = auctions
+            .range(deps.storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                let (auction_id, state) = item?;
+                let status = super::status(&state, env.block.time);
+                Ok(AuctionResp {
+                    auction_id,
+                    commodity_uri: state.commodity_uri,
+                    owner: state.owner,
+                    bid_denom: state.bid_denom,
+                    end_time: state.end_time,
+                    status,
+                })
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+
+        Ok(ListAuctionsResp { auctions })
+    }
+}
+
+pub mod exec {
+    use std::ops::{Add, Sub};
+    use std::str::FromStr;
+
+    use cosmwasm_std::{
+        DepsMut, Env, MessageInfo, Response, BankMsg, coins, Uint128, Addr, CosmosMsg, WasmMsg, StdResult, coin,
+        from_binary, to_binary, Event, Order, Binary, Storage, Decimal,
+    };
+    use cw_storage_plus::Bound;
+    use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+    use cw721::{Cw721ExecuteMsg, Cw721ReceiveMsg};
+
+    use crate::error::ContractError;
+    use crate::msg::{
+        AuctionStatus, AuctionType, CommissionPolicy, InstantiateMsg, Nft, ReservePrice, ReceiveMsg, SettlementStatus,
+    };
+    use crate::state::{
+        WINNER, PENDING_OWNER, BID_SEQ, SETTLEMENT, REFUND_CURSOR, COMMITMENTS, ALLOCATIONS, CLAIMS, Bid, BidRecord,
+        Settlement, State, UnitBid, Winner, auctions, bids, bid_log, unit_bids,
+    };
+
+    use super::{
+        bid_commitment, commission, current_price, min_next_bid, payouts, reserve_commitment, status, top_bids,
+        transition, Action,
+    };
+
+    // Transfer in the auction's native denom or CW20 token
+    fn payment_msg(state: &State, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+        let msg = match &state.cw20_addr {
+            Some(token) => WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer { recipient: recipient.to_string(), amount })?,
+                funds: vec![],
+            }
+            .into(),
+            None => BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins(amount.u128(), &state.bid_denom),
+            }
+            .into(),
+        };
+        Ok(msg)
+    }
+
+    fn payout_msgs(state: &State, commission: Uint128, proceeds: Uint128) -> StdResult<Vec<CosmosMsg>> {
+        payouts(state, commission, proceeds)
+            .into_iter()
+            .map(|(address, amount)| payment_msg(state, &address, amount))
+            .collect()
+    }
+
+    // Returns a bid to its bidder, the escrowed commission included.
+    // Sealed bid deposits never revealed come back without the penalty, paid to the owner.
+    // Cancelled auctions never opened the reveal phase, so their deposits come back in full.
+    fn refund_msgs(state: &State, bid: &Bid, recipient: &Addr) -> StdResult<(Uint128, Vec<CosmosMsg>)> {
+        let penalty = match state.auction_type.sealed() {
+            Some(i) if state.status != AuctionStatus::Cancelled => bid.deposit * i.penalty,
+            _ => Uint128::zero(),
+        };
+        let amount = bid.held() - penalty;
+
+        let mut msgs = vec![];
+        if !amount.is_zero() {
+            msgs.push(payment_msg(state, recipient, amount)?);
+        }
+        msgs.extend(payout_msgs(state, Uint128::zero(), penalty)?);
+        Ok((amount, msgs))
+    }
+
+    // Commission charged as a bid is placed, the rest counting as bid
+    fn placement_commission(state: &State, amount: Uint128) -> Result<Uint128, ContractError> {
+        match state.commission_policy {
+            CommissionPolicy::WinnerOnly => Ok(Uint128::zero()),
+            _ => commission(amount, state.bid_comission),
+        }
+    }
+
+    // Smallest amount to send for a bid counting at least `bid` once its commission is charged
+    fn placement_amount(state: &State, bid: Uint128) -> Result<Uint128, ContractError> {
+        if state.bid_comission >= Decimal::one() {
+            return Ok(Uint128::MAX);
+        }
+
+        // Commission is rounded up, so the estimate can fall a little short
+        let estimate = Decimal::from_atomics(bid, 0)?
+            .checked_div(Decimal::one() - state.bid_comission)?
+            .floor();
+        let mut amount = Uint128::from_str(&estimate.to_string())?;
+        while amount - placement_commission(state, amount)? < bid {
+            amount = amount.checked_add(Uint128::one())?;
+        }
+        Ok(amount)
+    }
+
+    // Appends the bid to the auction's bid history, returning its sequence id
+    fn log_bid(
+        storage: &mut dyn Storage,
+        env: &Env,
+        auction_id: u64,
+        bidder: &Addr,
+        amount: Uint128,
+        commission: Uint128,
+    ) -> StdResult<u64> {
+        let seq = BID_SEQ.may_load(storage, auction_id)?.unwrap_or_default() + 1;
+        BID_SEQ.save(storage, auction_id, &seq)?;
+        let record = BidRecord {
+            auction_id,
+            bidder: bidder.clone(),
+            amount,
+            commission,
+            net_amount: amount - commission,
+            height: env.block.height,
+            time: env.block.time,
+        };
+        bid_log().save(storage, (auction_id, seq), &record)?;
+        Ok(seq)
+    }
+
+    // Sealed bids only hold a commitment until revealed, and unit bids never set a winner
+    fn has_bids(storage: &dyn Storage, auction_id: u64) -> bool {
+        let committed = COMMITMENTS
+            .prefix(auction_id)
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        let bid_units = unit_bids()
+            .prefix(auction_id)
+            .keys(storage, None, None, Order::Ascending)
+            .next()
+            .is_some();
+        committed || bid_units || WINNER.has(storage, auction_id)
+    }
+
+    fn nft_transfer_msg(nft: &Nft, recipient: &Addr) -> StdResult<WasmMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: nft.contract.to_string(),
+            msg: to_binary(&Cw721ExecuteMsg::TransferNft {
+                recipient: recipient.to_string(),
+                token_id: nft.token_id.clone(),
+            })?,
+            funds: vec![],
+        })
+    }
+
+    fn settlement_event(auction_id: u64, status: &SettlementStatus) -> Event {
+        let status = match status {
+            SettlementStatus::Pending => "pending",
+            SettlementStatus::Delivered => "delivered",
+            SettlementStatus::Cancelled => "cancelled",
+        };
+        Event::new("settlement")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("status", status)
+    }
+
+    pub fn create_auction(deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg) -> Result<Response, ContractError> {
+        let auction_id = super::create_auction(deps, &env, info.sender.clone(), msg, None)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "create_auction")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("creator", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn receive_nft(deps: DepsMut, env: Env, info: MessageInfo, msg: Cw721ReceiveMsg) -> Result<Response, ContractError> {
+        let auction: InstantiateMsg = from_binary(&msg.msg)?;
+        let creator = deps.api.addr_validate(&msg.sender)?;
+
+        // The sender is the NFT contract, which already transferred the token to us
+        let nft = Nft { contract: info.sender, token_id: msg.token_id };
+        let auction_id = super::create_auction(deps, &env, creator.clone(), auction, Some(nft.clone()))?;
+
+        let resp = Response::new()
+            .add_attribute("action", "create_auction")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("creator", creator.as_str())
+            .add_attribute("nft_contract", nft.contract.as_str())
+            .add_attribute("token_id", nft.token_id);
+
+        Ok(resp)
+    }
+
+    pub fn bid(deps: DepsMut, env: Env, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        // Auctions priced in a CW20 token only take bids through `Receive`
+        if state.cw20_addr.is_some() {
+            return Err(ContractError::InvalidBidToken {});
+        }
+
+        let amount = info.funds.iter().find(|coin| coin.denom == state.bid_denom);
+
+        let coin_bid = match amount {
+            Some(i) => i,
+            None => return Err(ContractError::InvalidBidZeroAmount {}),
+        };
+
+        place_bid(deps, env, state, info.sender, auction_id, coin_bid.amount, false)
+    }
+
+    pub fn buy_now(deps: DepsMut, env: Env, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        if state.cw20_addr.is_some() {
+            return Err(ContractError::InvalidBidToken {});
+        }
+
+        let amount = info.funds
+            .iter()
+            .find(|coin| coin.denom == state.bid_denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        place_bid(deps, env, state, info.sender, auction_id, amount, true)
+    }
+
+    pub fn commit_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        hash: Binary,
+    ) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        if state.cw20_addr.is_some() {
+            return Err(ContractError::InvalidBidToken {});
+        }
+
+        let deposit = info.funds
+            .iter()
+            .find(|coin| coin.denom == state.bid_denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        place_commitment(deps, env, state, info.sender, auction_id, deposit, hash)
+    }
+
+    pub fn bid_units(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        quantity: u64,
+        unit_price: Uint128,
+    ) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        if state.cw20_addr.is_some() {
+            return Err(ContractError::InvalidBidToken {});
+        }
+
+        let amount = info.funds
+            .iter()
+            .find(|coin| coin.denom == state.bid_denom)
+            .map(|coin| coin.amount)
+            .unwrap_or_default();
+
+        place_unit_bid(deps, env, state, info.sender, auction_id, amount, quantity, unit_price)
+    }
+
+    pub fn receive(deps: DepsMut, env: Env, info: MessageInfo, msg: Cw20ReceiveMsg) -> Result<Response, ContractError> {
+        let hook: ReceiveMsg = from_binary(&msg.msg)?;
+        let auction_id = match &hook {
+            ReceiveMsg::Bid { auction_id }
+            | ReceiveMsg::BuyNow { auction_id }
+            | ReceiveMsg::CommitBid { auction_id, .. }
+            | ReceiveMsg::BidUnits { auction_id, .. } => *auction_id,
+        };
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        // The sender is the token contract, forwarding the bidder's tokens
+        if state.cw20_addr.as_ref() != Some(&info.sender) {
+            return Err(ContractError::InvalidBidToken {});
+        }
+
+        let bidder = deps.api.addr_validate(&msg.sender)?;
+        match hook {
+            ReceiveMsg::Bid { .. } => place_bid(deps, env, state, bidder, auction_id, msg.amount, false),
+            ReceiveMsg::BuyNow { .. } => place_bid(deps, env, state, bidder, auction_id, msg.amount, true),
+            ReceiveMsg::CommitBid { hash, .. } => place_commitment(deps, env, state, bidder, auction_id, msg.amount, hash),
+            ReceiveMsg::BidUnits { quantity, unit_price, .. } => {
+                place_unit_bid(deps, env, state, bidder, auction_id, msg.amount, quantity, unit_price)
+            },
+        }
+    }
+
+    fn place_commitment(
+        deps: DepsMut,
+        env: Env,
+        mut state: State,
+        bidder: Addr,
+        auction_id: u64,
+        deposit: Uint128,
+        hash: Binary,
+    ) -> Result<Response, ContractError> {
+        if deposit.is_zero() {
+            return Err(ContractError::InvalidBidZeroAmount {});
+        }
+
+        transition(&mut state, env.block.time, Action::CommitBid)?;
+
+        if state.owner == bidder {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if PENDING_OWNER.may_load(deps.storage, auction_id)? == Some(bidder.clone()) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if COMMITMENTS.has(deps.storage, (auction_id, &bidder)) {
+            return Err(ContractError::AlreadyCommitted {});
+        }
+
+        let min_deposit = state.auction_type.sealed().map(|i| i.min_deposit).unwrap_or_default();
+        if deposit < min_deposit {
+            return Err(ContractError::InvalidDeposit { amount: deposit, required_amount: min_deposit });
+        }
+
+        // The deposit is held as the bid until revealed
+        COMMITMENTS.save(deps.storage, (auction_id, &bidder), &hash)?;
+        bids().save(deps.storage, (auction_id, &bidder), &Bid { deposit, ..Bid::empty(auction_id) })?;
+
+        let mut resp = Response::new()
+            .add_attribute("action", "commit_bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("bidder", bidder.as_str());
+
+        resp = match &state.cw20_addr {
+            Some(token) => resp
+                .add_attribute("deposit", deposit.to_string())
+                .add_attribute("token", token.as_str()),
+            None => resp.add_attribute("deposit", coin(deposit.u128(), &state.bid_denom).to_string()),
+        };
+
+        Ok(resp)
+    }
+
+    // A new unit bid replaces the bidder's previous one, which is refunded
+    #[allow(clippy::too_many_arguments)]
+    fn place_unit_bid(
+        deps: DepsMut,
+        env: Env,
+        mut state: State,
+        bidder: Addr,
+        auction_id: u64,
+        amount: Uint128,
+        quantity: u64,
+        unit_price: Uint128,
+    ) -> Result<Response, ContractError> {
+        let mut resp = Response::new();
+
+        if amount.is_zero() {
+            return Err(ContractError::InvalidBidZeroAmount {});
+        }
+
+        transition(&mut state, env.block.time, Action::BidUnits)?;
+
+        if state.owner == bidder {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if PENDING_OWNER.may_load(deps.storage, auction_id)? == Some(bidder.clone()) {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let units = match &state.auction_type {
+            AuctionType::MultiUnit(i) => i.units,
+            _ => 0,
+        };
+        if quantity == 0 || quantity > units {
+            return Err(ContractError::InvalidBidQuantity { max: units });
+        }
+
+        let required_amount = unit_price.checked_mul(quantity.into())?;
+        if amount != required_amount {
+            return Err(ContractError::InvalidBidAmount { amount, required_amount });
+        }
+
+        let previous = bids()
+            .may_load(deps.storage, (auction_id, &bidder))?
+            .unwrap_or(Bid::empty(auction_id));
+        if !previous.amount.is_zero() {
+            resp = resp.add_message(payment_msg(&state, &bidder, previous.amount)?);
+        }
+
+        bids().save(deps.storage, (auction_id, &bidder), &Bid { amount, ..Bid::empty(auction_id) })?;
+        let seq = log_bid(deps.storage, &env, auction_id, &bidder, amount, Uint128::zero())?;
+        let unit_bid = UnitBid { auction_id, quantity, unit_price, seq };
+        unit_bids().save(deps.storage, (auction_id, &bidder), &unit_bid)?;
+
+        resp = resp
+            .add_attribute("action", "bid_units")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("bidder", bidder.as_str())
+            .add_attribute("seq", seq.to_string())
+            .add_attribute("quantity", quantity.to_string())
+            .add_attribute("unit_price", unit_price.to_string())
+            .add_attribute("refund", previous.amount.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn reveal_bid(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    ) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+        let mut resp = Response::new();
+
+        transition(&mut state, env.block.time, Action::RevealBid)?;
 
-    STATE.save(
-        deps.storage,
-        &State {
-            commodity_uri,
-            owner: contract_owner,
-            bid_comission,
-            is_closed: false,
-        },
-    )?;
+        // Ownership may have changed hands since the commitment
+        if state.owner == info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
 
-    Ok(Response::new())
-}
+        if PENDING_OWNER.may_load(deps.storage, auction_id)? == Some(info.sender.clone()) {
+            return Err(ContractError::Unauthorized {});
+        }
 
-pub mod query {
-    use cosmwasm_std::{Deps, StdResult};
+        let commitment = COMMITMENTS.may_load(deps.storage, (auction_id, &info.sender))?;
+        let mut bid = bids()
+            .may_load(deps.storage, (auction_id, &info.sender))?
+            .unwrap_or(Bid::empty(auction_id));
+        if commitment != Some(bid_commitment(&info.sender, amount, &salt)) || amount > bid.deposit {
+            return Err(ContractError::InvalidBidReveal {});
+        }
+
+        let amount_commission = placement_commission(&state, amount)?;
+        let excess = bid.deposit - amount;
+        bid.amount = amount - amount_commission;
+        bid.deposit = Uint128::zero();
+        if state.commission_policy == CommissionPolicy::EscrowRefundLosers {
+            bid.commission = amount_commission;
+        }
 
-    use crate::msg::{TotalUserBidResp, HighestBidResp, IsClosedResp, WinnerResp};
-    use crate::state::{STATE, BIDS, WINNER};
+        COMMITMENTS.remove(deps.storage, (auction_id, &info.sender));
+        bids().save(deps.storage, (auction_id, &info.sender), &bid)?;
 
-    pub fn total_user_bid(deps: Deps, address: String) -> StdResult<TotalUserBidResp> {
-        let address = deps.api.addr_validate(&address)?;
-        let amount = BIDS
-            .may_load(deps.storage, &address)?
+        // Highest revealed bid wins, the earliest one on ties
+        let winner_amount = WINNER
+            .may_load(deps.storage, auction_id)?
+            .map(|i| i.amount)
             .unwrap_or_default();
-        Ok(TotalUserBidResp { amount })
-    }
-
-    // we show this even if bid is closed
-    pub fn highest_bid(deps: Deps) -> StdResult<Option<HighestBidResp>> {
-        let winner = WINNER.may_load(deps.storage)?;
-        match winner {
-            Some(i) => Ok(Some(HighestBidResp { address: i.address, amount: i.amount })),
-            None => Ok(None),
+        if bid.amount > winner_amount {
+            WINNER.save(deps.storage, auction_id, &Winner { address: info.sender.clone(), amount: bid.amount })?;
         }
-    }
 
-    pub fn is_closed(deps: Deps) -> StdResult<IsClosedResp> {
-        let closed = STATE.load(deps.storage)?.is_closed;
-        Ok(IsClosedResp { closed })
-    }
+        let seq = log_bid(deps.storage, &env, auction_id, &info.sender, amount, amount_commission)?;
 
-    pub fn winner(deps: Deps) -> StdResult<Option<WinnerResp>> {
-        let closed = STATE.load(deps.storage)?.is_closed;
-        if closed == false {
-            return Ok(None);
+        // Return the part of the deposit above the bid
+        if !excess.is_zero() {
+            resp = resp.add_message(payment_msg(&state, &info.sender, excess)?);
         }
 
-        let winner = WINNER.may_load(deps.storage)?;
-        match winner {
-            Some(i) => Ok(Some(WinnerResp { address: i.address, amount: i.amount })),
-            None => Ok(None),
+        if state.commission_policy == CommissionPolicy::OnEveryBid {
+            resp = resp.add_messages(payout_msgs(&state, amount_commission, Uint128::zero())?);
         }
+
+        resp = resp
+            .add_attribute("action", "reveal_bid")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("bidder", info.sender.as_str())
+            .add_attribute("seq", seq.to_string())
+            .add_attribute("amount", amount.to_string())
+            .add_attribute("refund", excess.to_string());
+
+        Ok(resp)
     }
-}
 
-pub mod exec {
-    use std::ops::{Sub, Add};
-    use std::str::FromStr;
+    // With `buy_now` the bid must reach the buy-now price
+    fn place_bid(
+        deps: DepsMut,
+        env: Env,
+        mut state: State,
+        bidder: Addr,
+        auction_id: u64,
+        amount: Uint128,
+        buy_now: bool,
+    ) -> Result<Response, ContractError> {
+        let mut resp = Response::new();
 
-    use cosmwasm_std::{
-        DepsMut, MessageInfo, Response, BankMsg, coins, Uint128, Decimal,
-    };
+        if amount.is_zero() {
+            return Err(ContractError::InvalidBidZeroAmount {});
+        }
 
-    use crate::error::ContractError;
-    use crate::state::{STATE, WINNER, BIDS, BID_DENOM, Winner};
+        transition(&mut state, env.block.time, Action::Bid)?;
 
-    pub fn bid(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        let state = STATE.load(deps.storage)?;
-        let mut resp = Response::new();
+        if buy_now && state.buy_now_price.is_none() {
+            return Err(ContractError::UnsupportedAction { action: "buy_now".to_string() });
+        }
 
-        if state.is_closed {
-            return Err(ContractError::UnauthorizedWhileClosed {});
+        if state.owner == bidder {
+            return Err(ContractError::Unauthorized {});
         }
 
-        if state.owner == info.sender {
+        if PENDING_OWNER.may_load(deps.storage, auction_id)? == Some(bidder.clone()) {
             return Err(ContractError::Unauthorized {});
         }
 
-        let current_winner = WINNER.may_load(deps.storage)?;
+        // The first bid meeting the price of a Dutch auction wins it
+        if let Some(price) = current_price(&state, &env.block) {
+            if amount < price {
+                return Err(ContractError::InvalidBidAmount { amount, required_amount: price });
+            }
+            let resp = sell_now(deps, &env, state, &bidder, auction_id, amount, price)?;
+            return Ok(resp.add_attribute("action", "bid"));
+        }
+
+        let current_winner = WINNER.may_load(deps.storage, auction_id)?;
         let winner_amount = match current_winner {
             Some(i) => i.amount,
             None => Uint128::zero(),
         };
-        let mut user_bid = BIDS
-            .may_load(deps.storage, &info.sender)?
-            .unwrap_or_default();
-
-        let amount = info.funds.iter().find(|coin| coin.denom == BID_DENOM.to_string());
-
-        let coin_bid = match amount {
-            Some(i) => i,
-            None => return Err(ContractError::InvalidBidZeroAmount {}),
-        };
+        let mut user_bid = bids()
+            .may_load(deps.storage, (auction_id, &bidder))?
+            .unwrap_or(Bid::empty(auction_id));
 
         // Calculate owner's commission from bid amount
-        let commission = Decimal::from_atomics(coin_bid.amount, 0)?
-            .checked_mul(state.bid_comission)?
-            .ceil();
+        let previous_amount = user_bid.amount;
+        let mut amount_commission = placement_commission(&state, amount)?;
+        let mut amount_bid = amount.sub(amount_commission);
+        user_bid.amount = user_bid.amount.checked_add(amount_bid)?;
 
-        let amount_commission = Uint128::from_str(&commission.to_string())?;
-        let amount_bid = coin_bid.amount.sub(amount_commission);
-        user_bid = user_bid.checked_add(amount_bid)?;
+        // Only accept bids beating the current winner by the minimum increment,
+        // unless they reach the buy-now price
+        let buy_now_price = state.buy_now_price.filter(|price| user_bid.amount >= *price);
+        let mut overpaid = Uint128::zero();
+        match buy_now_price {
+            // Commission is only charged on what it takes to reach the price, the rest is returned as sent
+            Some(price) if !amount_commission.is_zero() => {
+                let needed = placement_amount(&state, price.saturating_sub(previous_amount))?.min(amount);
+                overpaid = amount - needed;
+                amount_commission = placement_commission(&state, needed)?;
+                amount_bid = needed - amount_commission;
+                user_bid.amount = previous_amount.checked_add(amount_bid)?;
+            },
+            Some(_) => {},
+            None => {
+                let min_bid = match (buy_now, state.buy_now_price) {
+                    (true, Some(price)) => price,
+                    _ => min_next_bid(&state.min_increment, winner_amount),
+                };
+                if user_bid.amount.lt(&min_bid) {
+                    let required_amount = min_bid
+                        .sub(user_bid.amount)
+                        .add(amount_bid);
+                    return Err(ContractError::InvalidBidAmount {amount: amount_bid, required_amount})
+                }
+            },
+        }
 
-        // Only accept bids higher than current winner
-        if !winner_amount.lt(&user_bid) {
-            let required_amount = winner_amount
-                .sub(user_bid)
-                .add(amount_bid)
-                .add(Uint128::one());
-            return Err(ContractError::InvalidBidAmount {amount: amount_bid, required_amount})
+        if state.commission_policy == CommissionPolicy::EscrowRefundLosers {
+            user_bid.commission = user_bid.commission.checked_add(amount_commission)?;
         }
 
         // Save the bid & update winner
-        BIDS.save(deps.storage, &info.sender, &user_bid)?;
-        let winner = Winner{amount: user_bid, address: info.sender.clone()};
-        WINNER.save(deps.storage, &winner)?;
+        bids().save(deps.storage, (auction_id, &bidder), &user_bid)?;
+        let winner = Winner{amount: user_bid.amount, address: bidder.clone()};
+        WINNER.save(deps.storage, auction_id, &winner)?;
+
+        // Log the individual bid
+        let seq = log_bid(deps.storage, &env, auction_id, &bidder, amount - overpaid, amount_commission)?;
+
+        if let Some(price) = buy_now_price {
+            resp = sell_at_buy_now_price(deps.storage, &env, &mut state, resp, auction_id, price, overpaid)?;
+        } else if let (Some(end_time), Some(extension)) = (state.end_time, &state.extension) {
+            // Extend the end time for bids placed right before it
+            if env.block.time.plus_seconds(extension.window) >= end_time {
+                state.end_time = Some(end_time.plus_seconds(extension.duration));
+                auctions().save(deps.storage, auction_id, &state)?;
+            }
+        }
+
+        // Send bidder's commission to owner and fee recipients
+        if state.commission_policy == CommissionPolicy::OnEveryBid {
+            resp = resp.add_messages(payout_msgs(&state, amount_commission, Uint128::zero())?);
+        }
+
+        resp = resp
+            .add_attribute("action", if buy_now { "buy_now" } else { "bid" })
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("bidder", bidder.as_str())
+            .add_attribute("seq", seq.to_string());
+
+        resp = match &state.cw20_addr {
+            Some(token) => resp
+                .add_attribute("amount", amount.to_string())
+                .add_attribute("token", token.as_str()),
+            None => resp.add_attribute("amount", coin(amount.u128(), &state.bid_denom).to_string()),
+        };
+
+        if let Some(end_time) = state.end_time {
+            resp = resp.add_attribute("end_time", end_time.to_string());
+        }
+
+        Ok(resp)
+    }
 
-        // Send bidder's commission to owner
-        let bank_msg = BankMsg::Send {
-            to_address: state.owner.to_string(),
-            amount: coins(amount_commission.u128(), BID_DENOM),
+    // Pays the sale price to the owner and fee recipients, settling the winner's commission
+    // unless already paid while bidding. Keeps what the winner bid above the price, for them to retract.
+    fn pay_sale(
+        storage: &mut dyn Storage,
+        state: &State,
+        auction_id: u64,
+        winner: &Winner,
+        price: Uint128,
+    ) -> Result<(Uint128, Vec<CosmosMsg>), ContractError> {
+        let winner_bid = bids().load(storage, (auction_id, &winner.address))?;
+        let amount_commission = match state.commission_policy {
+            CommissionPolicy::OnEveryBid => Uint128::zero(),
+            CommissionPolicy::WinnerOnly => commission(price, state.bid_comission)?,
+            CommissionPolicy::EscrowRefundLosers => winner_bid.commission,
         };
+        let proceeds = match state.commission_policy {
+            CommissionPolicy::WinnerOnly => price.checked_sub(amount_commission)?,
+            _ => price,
+        };
+
+        let refund = Bid { amount: winner.amount - price, ..Bid::empty(auction_id) };
+        bids().save(storage, (auction_id, &winner.address), &refund)?;
+
+        Ok((amount_commission, payout_msgs(state, amount_commission, proceeds)?))
+    }
+
+    // Closes the auction for a bid reaching the buy-now price, paying the owner like `close`
+    // and refunding the buyer whatever they bid above the price, along with what they sent
+    // beyond the bid reaching it
+    fn sell_at_buy_now_price(
+        storage: &mut dyn Storage,
+        env: &Env,
+        state: &mut State,
+        resp: Response,
+        auction_id: u64,
+        price: Uint128,
+        overpaid: Uint128,
+    ) -> Result<Response, ContractError> {
+        transition(state, env.block.time, Action::Sell)?;
+        auctions().save(storage, auction_id, state)?;
+
+        let settlement = Settlement { status: SettlementStatus::Pending, closed_at: env.block.time, price: Some(price) };
+        SETTLEMENT.save(storage, auction_id, &settlement)?;
+
+        let winner = WINNER.load(storage, auction_id)?;
+        let buyer = &winner.address;
+        let (amount_commission, msgs) = pay_sale(storage, state, auction_id, &winner, price)?;
+        let excess = (winner.amount - price).checked_add(overpaid)?;
+        bids().save(storage, (auction_id, buyer), &Bid::empty(auction_id))?;
+
+        let event = Event::new("buy_now")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("buyer", buyer.as_str())
+            .add_attribute("price", price.to_string())
+            .add_attribute("commission", amount_commission.to_string())
+            .add_attribute("refund", excess.to_string());
+
+        let mut resp = resp
+            .add_messages(msgs)
+            .add_event(settlement_event(auction_id, &settlement.status))
+            .add_event(event);
+
+        if !excess.is_zero() {
+            resp = resp.add_message(payment_msg(state, buyer, excess)?);
+        }
+
+        Ok(resp)
+    }
+
+    // Sells the commodity at the given price right away, returning whatever was sent above it
+    fn sell_now(
+        deps: DepsMut,
+        env: &Env,
+        mut state: State,
+        buyer: &Addr,
+        auction_id: u64,
+        amount: Uint128,
+        price: Uint128,
+    ) -> Result<Response, ContractError> {
+        transition(&mut state, env.block.time, Action::Sell)?;
+        auctions().save(deps.storage, auction_id, &state)?;
+
+        let amount_commission = commission(price, state.bid_comission)?;
+        WINNER.save(deps.storage, auction_id, &Winner { address: buyer.clone(), amount: price })?;
+        let seq = log_bid(deps.storage, env, auction_id, buyer, price, amount_commission)?;
+
+        let settlement = Settlement { status: SettlementStatus::Pending, closed_at: env.block.time, price: None };
+        SETTLEMENT.save(deps.storage, auction_id, &settlement)?;
+
+        let mut resp = Response::new()
+            .add_messages(payout_msgs(&state, amount_commission, price - amount_commission)?)
+            .add_event(settlement_event(auction_id, &settlement.status));
+
+        let excess = amount - price;
+        if !excess.is_zero() {
+            resp = resp.add_message(payment_msg(&state, buyer, excess)?);
+        }
 
         resp = resp
-            .add_message(bank_msg)
-            .add_attribute("action", "bid")
-            .add_attribute("bidder", info.sender.as_str())
-            .add_attribute("amount", info.funds[0].to_string());
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("buyer", buyer.as_str())
+            .add_attribute("seq", seq.to_string())
+            .add_attribute("price", price.to_string())
+            .add_attribute("commission", amount_commission.to_string())
+            .add_attribute("refund", excess.to_string());
 
         Ok(resp)
     }
 
-    pub fn close(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
-        let mut state = STATE.load(deps.storage)?;
+    pub fn close(deps: DepsMut, env: Env, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
         let mut resp = Response::new();
 
-        if state.is_closed {
-            return Err(ContractError::UnauthorizedWhileClosed {});
+        // Anyone can close the bid once its end time has passed
+        if state.owner != info.sender && status(&state, env.block.time) != AuctionStatus::Ended {
+            return Err(ContractError::Unauthorized {});
         }
 
-        if state.owner != info.sender {
-            return Err(ContractError::Unauthorized {});
+        // The owner cannot close without revealing the reserve price.
+        // Anyone else closing after the end time treats it as not met.
+        let reserve = match &state.reserve_price {
+            Some(ReservePrice::Public(i)) => *i,
+            Some(ReservePrice::Hidden(_)) => {
+                if state.owner == info.sender {
+                    return Err(ContractError::ReserveNotRevealed {});
+                }
+                Uint128::MAX
+            },
+            None => Uint128::zero(),
+        };
+
+        if let AuctionType::MultiUnit(lot) = &state.auction_type {
+            let units = lot.units;
+            return close_multi_unit(deps, env, state, auction_id, units, reserve);
         }
 
+        let winner = WINNER.may_load(deps.storage, auction_id)?;
+        let action = match &winner {
+            Some(i) if i.amount < reserve => Action::CloseBelowReserve,
+            Some(_) => Action::Sell,
+            None => Action::CloseUnsold,
+        };
+
         // Save state as closed
-        state.is_closed = true;
-        STATE.save(deps.storage, &state)?;
+        transition(&mut state, env.block.time, action)?;
+        auctions().save(deps.storage, auction_id, &state)?;
+
+        let runner_up = match &winner {
+            Some(i) => top_bids(deps.storage, auction_id, 2)?
+                .into_iter()
+                .find(|(address, _)| *address != i.address),
+            None => None,
+        };
+
+        // A sold commodity waits for the winner to claim it.
+        // Without a sale the escrowed NFT goes back to the owner right away.
+        let sold = state.status == AuctionStatus::AwaitingSettlement;
+
+        // Second-price auctions charge the runner-up bid, or the reserve price if higher
+        let price = match state.auction_type {
+            AuctionType::Vickrey(_) if sold => {
+                let runner_up_amount = runner_up.as_ref().map(|(_, amount)| *amount).unwrap_or_default();
+                Some(runner_up_amount.max(reserve))
+            },
+            _ => None,
+        };
+
+        let settlement = Settlement {
+            status: if sold { SettlementStatus::Pending } else { SettlementStatus::Cancelled },
+            closed_at: env.block.time,
+            price,
+        };
+        SETTLEMENT.save(deps.storage, auction_id, &settlement)?;
+
+        if let (false, Some(nft)) = (sold, &state.nft) {
+            resp = resp.add_message(nft_transfer_msg(nft, &state.owner)?);
+        }
+        resp = resp.add_event(settlement_event(auction_id, &settlement.status));
 
         // If there is a winner, send the total bid amount to owner
-        let winner = WINNER.may_load(deps.storage)?;
         match winner {
+            Some(i) if !sold => {
+                // Every bidder, including the highest one, can retract
+                resp = resp
+                    .add_attribute("action", "close")
+                    .add_attribute("auction_id", auction_id.to_string())
+                    .add_attribute("reserve_met", "false")
+                    .add_attribute("highest_bidder", i.address.as_str())
+                    .add_attribute("amount", i.amount.to_string());
+            },
             Some(i) => {
-                // Store 0 for winner's bid
-                BIDS.save(deps.storage, &i.address, &Uint128::zero())?;
+                let price = settlement.price.unwrap_or(i.amount);
 
-                // Send winner's amount to owner
-                let bank_msg = BankMsg::Send {
-                    to_address: state.owner.to_string(),
-                    amount: coins(i.amount.u128(), BID_DENOM),
-                };
+                // Send winner's amount to owner and fee recipients
+                let (amount_commission, msgs) = pay_sale(deps.storage, &state, auction_id, &i, price)?;
                 resp = resp
-                    .add_message(bank_msg)
+                    .add_messages(msgs)
                     .add_attribute("action", "close")
+                    .add_attribute("auction_id", auction_id.to_string())
                     .add_attribute("winner", i.address.as_str())
-                    .add_attribute("amount", i.amount.to_string());
+                    .add_attribute("amount", i.amount.to_string())
+                    .add_attribute("price", price.to_string())
+                    .add_attribute("commission", amount_commission.to_string());
+
+                if let Some((address, amount)) = runner_up {
+                    resp = resp
+                        .add_attribute("runner_up", address.as_str())
+                        .add_attribute("runner_up_amount", amount.to_string());
+                }
             },
             None => {
                 resp = resp
                     .add_attribute("action", "close")
+                    .add_attribute("auction_id", auction_id.to_string())
             },
         }
 
         Ok(resp)
     }
 
-    pub fn retract(deps: DepsMut, info: MessageInfo, receiver: Option<String>) -> Result<Response, ContractError> {
-        let state = STATE.load(deps.storage)?;
+    // Allocates the lot to the highest unit prices at or above the reserve,
+    // the marginal bidder getting whatever units are left.
+    // Everyone pays the lowest accepted unit price.
+    fn close_multi_unit(
+        deps: DepsMut,
+        env: Env,
+        mut state: State,
+        auction_id: u64,
+        units: u64,
+        reserve: Uint128,
+    ) -> Result<Response, ContractError> {
         let mut resp = Response::new();
 
-        if state.is_closed == false {
-            return Err(ContractError::UnauthorizedWhileOpen {});
+        let ranked = unit_bids()
+            .idx
+            .price
+            .sub_prefix(auction_id)
+            .range(deps.storage, None, None, Order::Descending)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut remaining = units;
+        let mut allocations = vec![];
+        for ((_, address), unit_bid) in &ranked {
+            if remaining == 0 || unit_bid.unit_price < reserve {
+                break;
+            }
+            let allocated = unit_bid.quantity.min(remaining);
+            remaining -= allocated;
+            allocations.push((address.clone(), allocated, unit_bid.unit_price));
+        }
+        let clearing_price = allocations.last().map(|(_, _, unit_price)| *unit_price);
+
+        let action = match clearing_price {
+            Some(_) => Action::Sell,
+            None if ranked.is_empty() => Action::CloseUnsold,
+            None => Action::CloseBelowReserve,
+        };
+        transition(&mut state, env.block.time, action)?;
+        auctions().save(deps.storage, auction_id, &state)?;
+
+        let sold = state.status == AuctionStatus::AwaitingSettlement;
+        let settlement = Settlement {
+            status: if sold { SettlementStatus::Pending } else { SettlementStatus::Cancelled },
+            closed_at: env.block.time,
+            price: clearing_price,
+        };
+        SETTLEMENT.save(deps.storage, auction_id, &settlement)?;
+
+        // Keep what each winner bid above their allocation, for them to retract
+        let price = clearing_price.unwrap_or_default();
+        let mut total = Uint128::zero();
+        for (address, allocated, _) in &allocations {
+            let cost = price.checked_mul((*allocated).into())?;
+            let mut bid = bids().load(deps.storage, (auction_id, address))?;
+            bid.amount = bid.amount.checked_sub(cost)?;
+            bids().save(deps.storage, (auction_id, address), &bid)?;
+            ALLOCATIONS.save(deps.storage, (auction_id, address), allocated)?;
+            total = total.checked_add(cost)?;
+        }
+
+        let amount_commission = commission(total, state.bid_comission)?;
+        if sold {
+            resp = resp.add_messages(payout_msgs(&state, amount_commission, total - amount_commission)?);
+        }
+
+        resp = resp
+            .add_event(settlement_event(auction_id, &settlement.status))
+            .add_attribute("action", "close")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("units_sold", (units - remaining).to_string())
+            .add_attribute("winners", allocations.len().to_string());
+
+        if let Some(price) = clearing_price {
+            resp = resp
+                .add_attribute("clearing_price", price.to_string())
+                .add_attribute("amount", total.to_string())
+                .add_attribute("commission", amount_commission.to_string());
         }
 
+        Ok(resp)
+    }
+
+    pub fn retract(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        receiver: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+        let mut resp = Response::new();
+
+        transition(&mut state, env.block.time, Action::Retract)?;
+
         let funds_receiver = match receiver {
             Some(i) => deps.api.addr_validate(&i)?,
             None => info.sender.clone(),
         };
 
-        let amount = BIDS
-            .may_load(deps.storage, &info.sender)?
-            .unwrap_or_default();
+        let bid = bids()
+            .may_load(deps.storage, (auction_id, &info.sender))?
+            .unwrap_or(Bid::empty(auction_id));
 
-        if amount.is_zero() {
+        if bid.held().is_zero() {
             return Err(ContractError::InvalidRetractZeroAmount {});
         }
 
         // Store 0 for bidder
-        BIDS.save(deps.storage, &info.sender, &Uint128::zero())?;
+        bids().save(deps.storage, (auction_id, &info.sender), &Bid::empty(auction_id))?;
 
         // Send funds back to bidder
-        let bank_msg = BankMsg::Send {
-            to_address: funds_receiver.to_string(),
-            amount: coins(amount.u128(), BID_DENOM),
-        };
-
+        let (amount, msgs) = refund_msgs(&state, &bid, &funds_receiver)?;
         resp = resp
-            .add_message(bank_msg)
+            .add_messages(msgs)
             .add_attribute("action", "retract")
+            .add_attribute("auction_id", auction_id.to_string())
             .add_attribute("bidder", info.sender.as_str())
             .add_attribute("receiver", funds_receiver.as_str())
             .add_attribute("amount", amount.to_string());
 
         Ok(resp)
     }
+
+    const DEFAULT_REFUND_LIMIT: u32 = 10;
+    const MAX_REFUND_LIMIT: u32 = 30;
+
+    pub fn refund_all(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        limit: Option<u32>,
+    ) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+        let mut resp = Response::new();
+
+        transition(&mut state, env.block.time, Action::RefundAll)?;
+
+        let limit = limit.unwrap_or(DEFAULT_REFUND_LIMIT).min(MAX_REFUND_LIMIT) as usize;
+        let cursor = REFUND_CURSOR.may_load(deps.storage, auction_id)?;
+        let page = bids()
+            .prefix(auction_id)
+            .range(deps.storage, cursor.as_ref().map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<_>>>()?;
+
+        let mut refunded = 0;
+        for (bidder, bid) in &page {
+            if bid.held().is_zero() {
+                continue;
+            }
+
+            bids().save(deps.storage, (auction_id, bidder), &Bid::empty(auction_id))?;
+            resp = resp.add_messages(refund_msgs(&state, bid, bidder)?.1);
+            refunded += 1;
+        }
+
+        if let Some((bidder, _)) = page.last() {
+            REFUND_CURSOR.save(deps.storage, auction_id, bidder)?;
+        }
+
+        resp = resp
+            .add_attribute("action", "refund_all")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("sender", info.sender.as_str())
+            .add_attribute("refunded", refunded.to_string())
+            .add_attribute("finished", (page.len() < limit).to_string());
+
+        Ok(resp)
+    }
+
+    pub fn cancel(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        reason: Option<String>,
+    ) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+        let mut resp = Response::new();
+
+        transition(&mut state, env.block.time, Action::Cancel)?;
+
+        if state.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if state.cancel_requires_no_bids && has_bids(deps.storage, auction_id) {
+            return Err(ContractError::CancelWithBids {});
+        }
+
+        // Bids stay in place for every bidder, the highest one included, to retract
+        auctions().save(deps.storage, auction_id, &state)?;
+
+        let settlement = Settlement { status: SettlementStatus::Cancelled, closed_at: env.block.time, price: None };
+        SETTLEMENT.save(deps.storage, auction_id, &settlement)?;
+
+        if let Some(nft) = &state.nft {
+            resp = resp.add_message(nft_transfer_msg(nft, &state.owner)?);
+        }
+
+        resp = resp
+            .add_event(settlement_event(auction_id, &settlement.status))
+            .add_attribute("action", "cancel")
+            .add_attribute("auction_id", auction_id.to_string());
+
+        if let Some(reason) = reason {
+            resp = resp.add_attribute("reason", reason);
+        }
+
+        Ok(resp)
+    }
+
+    pub fn claim(deps: DepsMut, env: Env, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+        transition(&mut state, env.block.time, Action::Claim)?;
+
+        let mut settlement = SETTLEMENT.load(deps.storage, auction_id)?;
+
+        // Multi-unit lots have no single winner, any bidder with an allocation can claim
+        let winner = WINNER.may_load(deps.storage, auction_id)?;
+        let is_winner = match &winner {
+            Some(i) => i.address == info.sender,
+            None => ALLOCATIONS.has(deps.storage, (auction_id, &info.sender)),
+        };
+
+        // Anyone can deliver to the winner once the grace period has passed
+        let grace_ended = match state.claim_grace_period {
+            Some(i) => env.block.time >= settlement.closed_at.plus_seconds(i),
+            None => false,
+        };
+        if !is_winner && !grace_ended {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        // Each bidder claims their own units, the lot is settled once every allocation is claimed.
+        // Past the grace period anyone else settles the whole lot at once.
+        let mut settled = true;
+        if winner.is_none() && is_winner {
+            if CLAIMS.has(deps.storage, (auction_id, &info.sender)) {
+                return Err(ContractError::AlreadyClaimed {});
+            }
+            CLAIMS.save(deps.storage, (auction_id, &info.sender), &true)?;
+
+            let winners = ALLOCATIONS
+                .prefix(auction_id)
+                .keys(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            settled = winners.iter().all(|address| CLAIMS.has(deps.storage, (auction_id, address)));
+        }
+
+        let mut resp = Response::new();
+        if settled {
+            auctions().save(deps.storage, auction_id, &state)?;
+            settlement.status = SettlementStatus::Delivered;
+            SETTLEMENT.save(deps.storage, auction_id, &settlement)?;
+            resp = resp.add_event(settlement_event(auction_id, &settlement.status));
+        }
+
+        if let (Some(nft), Some(winner)) = (&state.nft, &winner) {
+            resp = resp.add_message(nft_transfer_msg(nft, &winner.address)?);
+        }
+
+        resp = resp
+            .add_attribute("action", "claim")
+            .add_attribute("auction_id", auction_id.to_string());
+
+        if let Some(winner) = winner {
+            resp = resp.add_attribute("winner", winner.address.as_str());
+        }
+        resp = resp.add_attribute("sender", info.sender.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn reveal_reserve(
+        deps: DepsMut,
+        env: Env,
+        info: MessageInfo,
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    ) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+
+        transition(&mut state, env.block.time, Action::RevealReserve)?;
+
+        if state.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        match &state.reserve_price {
+            Some(ReservePrice::Hidden(commitment)) if *commitment == reserve_commitment(amount, &salt) => {},
+            _ => return Err(ContractError::InvalidReserveReveal {}),
+        }
+
+        state.reserve_price = Some(ReservePrice::Public(amount));
+        auctions().save(deps.storage, auction_id, &state)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "reveal_reserve")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("amount", amount.to_string());
+
+        Ok(resp)
+    }
+
+    pub fn propose_new_owner(deps: DepsMut, info: MessageInfo, auction_id: u64, new_owner: String) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        if state.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let new_owner = deps.api.addr_validate(&new_owner)?;
+
+        // Owners cannot bid, so neither can owners-to-be, sealed commitments included
+        let held = bids()
+            .may_load(deps.storage, (auction_id, &new_owner))?
+            .map(|i| i.held())
+            .unwrap_or_default();
+        if !held.is_zero() {
+            return Err(ContractError::PendingOwnerIsBidder {});
+        }
+
+        PENDING_OWNER.save(deps.storage, auction_id, &new_owner)?;
+
+        let resp = Response::new()
+            .add_attribute("action", "propose_new_owner")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("owner", state.owner.as_str())
+            .add_attribute("pending_owner", new_owner.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn accept_ownership(deps: DepsMut, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let mut state = auctions().load(deps.storage, auction_id)?;
+
+        let pending_owner = match PENDING_OWNER.may_load(deps.storage, auction_id)? {
+            Some(i) => i,
+            None => return Err(ContractError::NoPendingOwner {}),
+        };
+
+        if pending_owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let previous_owner = state.owner;
+        state.owner = pending_owner;
+        auctions().save(deps.storage, auction_id, &state)?;
+        PENDING_OWNER.remove(deps.storage, auction_id);
+
+        let resp = Response::new()
+            .add_attribute("action", "accept_ownership")
+            .add_attribute("auction_id", auction_id.to_string())
+            .add_attribute("previous_owner", previous_owner.as_str())
+            .add_attribute("owner", state.owner.as_str());
+
+        Ok(resp)
+    }
+
+    pub fn cancel_ownership_transfer(deps: DepsMut, info: MessageInfo, auction_id: u64) -> Result<Response, ContractError> {
+        let state = auctions().load(deps.storage, auction_id)?;
+
+        if state.owner != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        if PENDING_OWNER.may_load(deps.storage, auction_id)?.is_none() {
+            return Err(ContractError::NoPendingOwner {});
+        }
+
+        PENDING_OWNER.remove(deps.storage, auction_id);
+
+        let resp = Response::new()
+            .add_attribute("action", "cancel_ownership_transfer")
+            .add_attribute("auction_id", auction_id.to_string());
+
+        Ok(resp)
+    }
 }
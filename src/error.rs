@@ -1,6 +1,8 @@
 use cosmwasm_std::{StdError, Uint128, OverflowError, DecimalRangeExceeded, CheckedFromRatioError};
 use thiserror::Error;
 
+use crate::msg::AuctionStatus;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
@@ -15,21 +17,93 @@ pub enum ContractError {
     #[error("{0}")]
     CheckedFromRatioError(#[from] CheckedFromRatioError),
 
+    #[error("{0}")]
+    Semver(String),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
-    #[error("Unauthorized - bid is closed")]
-    UnauthorizedWhileClosed {},
+    #[error("Cannot {action} - auction is {status}")]
+    InvalidTransition { action: String, status: AuctionStatus },
+
+    #[error("Bid denom cannot be empty")]
+    InvalidBidDenom {},
+
+    #[error("Invalid bid window - end time must be after start time and current time")]
+    InvalidBidWindow {},
+
+    #[error("Invalid bid extension - requires an end time and a non-zero duration")]
+    InvalidBidExtension {},
+
+    #[error("Invalid bid token - not accepted by this auction")]
+    InvalidBidToken {},
+
+    #[error("Invalid fee recipients - shares must add up to at most 1")]
+    InvalidFeeShares {},
+
+    #[error("Invalid sealed auction - requires an end time, no extension, a reveal period, a penalty of at most 1 and a winner only commission for second-price auctions")]
+    InvalidSealedAuction {},
+
+    #[error("Invalid Dutch auction - the floor price must be between the reserve and start prices, decay must be non-zero, and block decay cannot start later")]
+    InvalidDutchAuction {},
+
+    #[error("Invalid buy-now price - requires an English auction and cannot be zero or below the reserve price")]
+    InvalidBuyNowPrice {},
+
+    #[error("Invalid multi-unit auction - requires at least one unit, no NFT, no extension or minimum increment and a winner only commission")]
+    InvalidMultiUnitAuction {},
+
+    #[error("Invalid bid quantity - must be between 1 and {max}")]
+    InvalidBidQuantity { max: u64 },
 
-    #[error("Unauthorized - bid is open")]
-    UnauthorizedWhileOpen {},
+    #[error("Cannot {action} - not supported by this auction type")]
+    UnsupportedAction { action: String },
 
-    #[error("Invalid bid amount. Found 0 ATOM")]
+    #[error("Bid already committed")]
+    AlreadyCommitted {},
+
+    #[error("Units already claimed")]
+    AlreadyClaimed {},
+
+    #[error("Invalid deposit - sent {amount}, required at least {required_amount}")]
+    InvalidDeposit { amount: Uint128, required_amount: Uint128 },
+
+    #[error("Invalid bid reveal - does not match the commitment or exceeds the deposit")]
+    InvalidBidReveal {},
+
+    #[error("Invalid bid amount. Found 0 of the accepted denom")]
     InvalidBidZeroAmount {},
 
     #[error("Invalid bid - sent {amount}, required at least {required_amount}")]
     InvalidBidAmount { amount: Uint128, required_amount: Uint128 },
 
-    #[error("Invalid retract amount. Found 0 ATOM")]
+    #[error("Invalid retract amount. Found 0")]
     InvalidRetractZeroAmount {},
+
+    #[error("Reserve price must be revealed before closing")]
+    ReserveNotRevealed {},
+
+    #[error("Invalid reserve price reveal")]
+    InvalidReserveReveal {},
+
+    #[error("No ownership transfer pending")]
+    NoPendingOwner {},
+
+    #[error("Cannot cancel - bids were already placed")]
+    CancelWithBids {},
+
+    #[error("Pending owner cannot be an active bidder")]
+    PendingOwnerIsBidder {},
+
+    #[error("Cannot migrate from a different contract - found {contract}")]
+    InvalidMigrationContract { contract: String },
+
+    #[error("Cannot migrate from version {from} to older version {to}")]
+    InvalidMigrationVersion { from: String, to: String },
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::Semver(err.to_string())
+    }
 }
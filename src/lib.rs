@@ -4,7 +4,7 @@ use cosmwasm_std::entry_point;
 use cosmwasm_std::{to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult};
 use error::ContractError;
 // use error::ContractError;
-use msg::{InstantiateMsg};
+use msg::{InstantiateMsg, MigrateMsg};
 
 mod contract;
 pub mod error;
@@ -16,17 +16,17 @@ mod state;
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
-    contract::instantiate(deps, info, msg.commodity_uri, msg.bid_comission, msg.owner)
+) -> Result<Response, ContractError> {
+    contract::instantiate(deps, env, info, msg)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _: Env,
+    env: Env,
     info: MessageInfo,
     msg: msg::ExecMsg,
 ) -> Result<Response, ContractError> {
@@ -34,21 +34,60 @@ pub fn execute(
     use msg::ExecMsg::*;
 
     match msg {
-        Bid {} => exec::bid(deps, info),
-        Close {} => exec::close(deps, info),
-        Retract { receiver } => exec::retract(deps, info, receiver),
+        CreateAuction(msg) => exec::create_auction(deps, env, info, *msg),
+        Bid { auction_id } => exec::bid(deps, env, info, auction_id),
+        BuyNow { auction_id } => exec::buy_now(deps, env, info, auction_id),
+        CommitBid { auction_id, hash } => exec::commit_bid(deps, env, info, auction_id, hash),
+        BidUnits { auction_id, quantity, unit_price } => {
+            exec::bid_units(deps, env, info, auction_id, quantity, unit_price)
+        },
+        RevealBid { auction_id, amount, salt } => exec::reveal_bid(deps, env, info, auction_id, amount, salt),
+        Close { auction_id } => exec::close(deps, env, info, auction_id),
+        Retract { auction_id, receiver } => exec::retract(deps, env, info, auction_id, receiver),
+        RefundAll { auction_id, limit } => exec::refund_all(deps, env, info, auction_id, limit),
+        RevealReserve { auction_id, amount, salt } => exec::reveal_reserve(deps, env, info, auction_id, amount, salt),
+        ProposeNewOwner { auction_id, new_owner } => exec::propose_new_owner(deps, info, auction_id, new_owner),
+        AcceptOwnership { auction_id } => exec::accept_ownership(deps, info, auction_id),
+        CancelOwnershipTransfer { auction_id } => exec::cancel_ownership_transfer(deps, info, auction_id),
+        Cancel { auction_id, reason } => exec::cancel(deps, env, info, auction_id, reason),
+        Claim { auction_id } => exec::claim(deps, env, info, auction_id),
+        Receive(msg) => exec::receive(deps, env, info, msg),
+        ReceiveNft(msg) => exec::receive_nft(deps, env, info, msg),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
+pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, ContractError> {
+    contract::migrate(deps, env, msg)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: msg::QueryMsg) -> StdResult<Binary> {
     use contract::query;
     use msg::QueryMsg::*;
 
     match msg {
-        TotalUserBid { address } => to_binary(&query::total_user_bid(deps, address)?),
-        HighestBid {} => to_binary(&query::highest_bid(deps)?),
-        IsClosed {} => to_binary(&query::is_closed(deps)?),
-        Winner {} => to_binary(&query::winner(deps)?),
+        TotalUserBid { auction_id, address } => to_binary(&query::total_user_bid(deps, auction_id, address)?),
+        HighestBid { auction_id } => to_binary(&query::highest_bid(deps, auction_id)?),
+        Status { auction_id } => to_binary(&query::status(deps, env, auction_id)?),
+        Winner { auction_id } => to_binary(&query::winner(deps, auction_id)?),
+        Config { auction_id } => to_binary(&query::config(deps, auction_id)?),
+        FeeConfig { auction_id } => to_binary(&query::fee_config(deps, auction_id)?),
+        EndTime { auction_id } => to_binary(&query::end_time(deps, auction_id)?),
+        MinNextBid { auction_id } => to_binary(&query::min_next_bid(deps, auction_id)?),
+        CurrentPrice { auction_id } => to_binary(&query::current_price(deps, env, auction_id)?),
+        Allocation { auction_id, address } => to_binary(&query::allocation(deps, auction_id, address)?),
+        Ownership { auction_id } => to_binary(&query::ownership(deps, auction_id)?),
+        Settlement { auction_id } => to_binary(&query::settlement(deps, auction_id)?),
+        AllBids { auction_id, start_after, limit, order_by } => {
+            to_binary(&query::all_bids(deps, auction_id, start_after, limit, order_by)?)
+        },
+        TopBids { auction_id, limit } => to_binary(&query::top_bids(deps, auction_id, limit)?),
+        BidHistory { auction_id, bidder, start_after, limit } => {
+            to_binary(&query::bid_history(deps, auction_id, bidder, start_after, limit)?)
+        },
+        ListAuctions { owner, closed, start_after, limit } => {
+            to_binary(&query::list_auctions(deps, env, owner, closed, start_after, limit)?)
+        },
     }
 }
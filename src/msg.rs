@@ -1,5 +1,9 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use std::fmt;
+
+use cosmwasm_std::{Addr, Binary, Decimal, Timestamp, Uint128};
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cw20::Cw20ReceiveMsg;
+use cw721::Cw721ReceiveMsg;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -7,6 +11,227 @@ pub struct InstantiateMsg {
     pub commodity_uri: String,
     pub owner: Option<String>,
     pub bid_comission: Decimal,
+    #[serde(default)]
+    pub bid_denom: String,
+    /// CW20 token bids are paid in, sent with `Receive`. Replaces `bid_denom`.
+    pub cw20_addr: Option<String>,
+    /// Bids are rejected before this time. Defaults to the instantiation time.
+    pub start_time: Option<Timestamp>,
+    /// Bids are rejected from this time on and anyone can close the bid.
+    /// Without it the bid stays open until the owner closes it.
+    pub end_time: Option<Timestamp>,
+    /// Extends the end time when bids arrive right before it. Requires `end_time`.
+    pub extension: Option<BidExtension>,
+    /// Minimum winning bid. Below it the bid closes without a sale.
+    pub reserve_price: Option<ReservePrice>,
+    /// How much a new highest bid must exceed the current one. Defaults to 1.
+    pub min_increment: Option<MinIncrement>,
+    /// A bid reaching this price, net of commission, wins right away and closes the auction.
+    /// English auctions only.
+    pub buy_now_price: Option<Uint128>,
+    /// When `bid_comission` is charged. Defaults to `OnEveryBid`.
+    pub commission_policy: Option<CommissionPolicy>,
+    /// Platform fees, each taking its share of `fee_base` before the owner is paid
+    pub fee_recipients: Option<Vec<FeeRecipient>>,
+    /// What the fee shares apply to. Defaults to `Commission`.
+    pub fee_base: Option<FeeBase>,
+    /// Seconds after close from which anyone can claim on behalf of the winner.
+    /// Without it only the winner can claim.
+    pub claim_grace_period: Option<u64>,
+    /// Only allow the owner to cancel before the first bid
+    #[serde(default)]
+    pub cancel_requires_no_bids: bool,
+    /// How bids are placed. Defaults to `English`.
+    pub auction_type: Option<AuctionType>,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum AuctionType {
+    /// Public bids, each beating the current highest one
+    #[default]
+    English,
+    /// Bids are committed as hashes until the end time, and revealed after it
+    Sealed(SealedBid),
+    /// Sealed bids where the winner pays the second highest bid, or the reserve price if higher.
+    /// Commission is only charged on that price, with the winner only commission policy.
+    Vickrey(SealedBid),
+    /// Descending price, the first bid meeting it wins and closes the auction
+    Dutch(DutchAuction),
+    /// Identical units sold to the highest per-unit bids at a single clearing price
+    MultiUnit(MultiUnitAuction),
+}
+
+impl AuctionType {
+    pub fn sealed(&self) -> Option<&SealedBid> {
+        match self {
+            AuctionType::English | AuctionType::Dutch(_) | AuctionType::MultiUnit(_) => None,
+            AuctionType::Sealed(i) | AuctionType::Vickrey(i) => Some(i),
+        }
+    }
+}
+
+#[cw_serde]
+pub struct SealedBid {
+    /// Minimum funds escrowed with a commitment. Revealed bids cannot exceed the deposit.
+    pub min_deposit: Uint128,
+    /// Seconds after the end time during which bids are revealed
+    pub reveal_period: u64,
+    /// Share of the deposit kept from bidders who do not reveal, paid to the owner
+    pub penalty: Decimal,
+}
+
+#[cw_serde]
+pub struct BidExtension {
+    /// Bids accepted within this many seconds before the end time extend it
+    pub window: u64,
+    /// Seconds added to the end time by each such bid
+    pub duration: u64,
+}
+
+#[cw_serde]
+pub struct DutchAuction {
+    pub start_price: Uint128,
+    /// The price never decays below it
+    pub floor_price: Uint128,
+    pub decay: PriceDecay,
+}
+
+#[cw_serde]
+pub struct MultiUnitAuction {
+    /// Units in the lot, all sold at the lowest accepted unit price.
+    /// The reserve price applies per unit. Commission is charged on the proceeds at close,
+    /// so only the winner only commission policy is accepted.
+    pub units: u64,
+}
+
+#[cw_serde]
+pub enum PriceDecay {
+    /// Falls evenly from the start price to the floor price over `duration` units
+    Linear { duration: u64, unit: DecayUnit },
+    /// Falls by `amount` every `interval` units
+    Stepwise { amount: Uint128, interval: u64, unit: DecayUnit },
+}
+
+#[cw_serde]
+pub enum DecayUnit {
+    /// Seconds from the start time
+    Seconds,
+    /// Blocks from the one the auction was created in, which must also open it
+    Blocks,
+}
+
+#[cw_serde]
+pub enum ReservePrice {
+    Public(Uint128),
+    /// sha256 of the reserve amount followed by a salt, revealed by the owner
+    /// with `RevealReserve` before closing
+    Hidden(Binary),
+}
+
+#[cw_serde]
+pub enum MinIncrement {
+    Absolute(Uint128),
+    /// Share of the current highest bid, rounded down
+    Percentage(Decimal),
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum CommissionPolicy {
+    /// Every bid pays commission to the owner right away, and only the rest counts as bid
+    #[default]
+    OnEveryBid,
+    /// Bids count in full and only the winning bid pays commission, at close
+    WinnerOnly,
+    /// Every bid pays commission, held by the contract until close. The winner's goes
+    /// to the owner and the others are returned with the retracted bids.
+    EscrowRefundLosers,
+}
+
+#[cw_serde]
+pub struct Nft {
+    pub contract: Addr,
+    pub token_id: String,
+}
+
+#[cw_serde]
+pub enum SettlementStatus {
+    /// Sold, waiting for the winner to claim the commodity
+    Pending,
+    Delivered,
+    /// Closed without a sale
+    Cancelled,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum AuctionStatus {
+    /// Bids are not accepted before the start time
+    Scheduled,
+    #[default]
+    Active,
+    /// Sealed bids are being revealed
+    Revealing,
+    /// The end time passed, waiting to be closed
+    Ended,
+    /// Sold, waiting for the winner to claim the commodity
+    AwaitingSettlement,
+    Settled,
+    /// Closed without a sale, the highest bid being below the reserve price
+    ReserveNotMet,
+    /// Closed without any bid
+    Unsold,
+    Cancelled,
+}
+
+impl AuctionStatus {
+    pub fn is_closed(&self) -> bool {
+        !matches!(
+            self,
+            AuctionStatus::Scheduled | AuctionStatus::Active | AuctionStatus::Revealing | AuctionStatus::Ended
+        )
+    }
+}
+
+impl fmt::Display for AuctionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            AuctionStatus::Scheduled => "scheduled",
+            AuctionStatus::Active => "active",
+            AuctionStatus::Revealing => "revealing",
+            AuctionStatus::Ended => "ended",
+            AuctionStatus::AwaitingSettlement => "awaiting_settlement",
+            AuctionStatus::Settled => "settled",
+            AuctionStatus::ReserveNotMet => "reserve_not_met",
+            AuctionStatus::Unsold => "unsold",
+            AuctionStatus::Cancelled => "cancelled",
+        };
+        f.write_str(status)
+    }
+}
+
+#[cw_serde]
+pub struct FeeRecipient {
+    pub address: String,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum FeeBase {
+    #[default]
+    Commission,
+    /// The winning bid paid to the owner at close
+    Proceeds,
+    All,
+}
+
+#[cw_serde]
+pub enum BidOrder {
+    Bidder,
+    /// Highest bids first, as in a leaderboard
+    Amount,
 }
 
 #[cw_serde]
@@ -14,26 +239,193 @@ pub struct InstantiateMsg {
 pub enum QueryMsg {
     #[returns(TotalUserBidResp)]
     TotalUserBid {
+        auction_id: u64,
         address: String,
     },
 
     #[returns(Option<HighestBidResp>)]
-    HighestBid {},
+    HighestBid {
+        auction_id: u64,
+    },
 
-    #[returns(IsClosedResp)]
-    IsClosed {},
+    #[returns(StatusResp)]
+    Status {
+        auction_id: u64,
+    },
 
     #[returns(Option<WinnerResp>)]
-    Winner {},
+    Winner {
+        auction_id: u64,
+    },
+
+    #[returns(ConfigResp)]
+    Config {
+        auction_id: u64,
+    },
+
+    #[returns(FeeConfigResp)]
+    FeeConfig {
+        auction_id: u64,
+    },
+
+    #[returns(EndTimeResp)]
+    EndTime {
+        auction_id: u64,
+    },
+
+    #[returns(MinNextBidResp)]
+    MinNextBid {
+        auction_id: u64,
+    },
+
+    /// Units of a multi-unit auction won by a bidder, known once closed
+    #[returns(Option<AllocationResp>)]
+    Allocation {
+        auction_id: u64,
+        address: String,
+    },
+
+    /// Price a bid must meet in an open Dutch auction
+    #[returns(Option<CurrentPriceResp>)]
+    CurrentPrice {
+        auction_id: u64,
+    },
+
+    #[returns(OwnershipResp)]
+    Ownership {
+        auction_id: u64,
+    },
+
+    /// Not set before the auction closes
+    #[returns(Option<SettlementResp>)]
+    Settlement {
+        auction_id: u64,
+    },
+
+    #[returns(AllBidsResp)]
+    AllBids {
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        /// Defaults to ordering by bidder address
+        order_by: Option<BidOrder>,
+    },
+
+    #[returns(TopBidsResp)]
+    TopBids { auction_id: u64, limit: Option<u32> },
+
+    /// Individual bids in the order they were placed, optionally of a single bidder
+    #[returns(BidHistoryResp)]
+    BidHistory {
+        auction_id: u64,
+        bidder: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(ListAuctionsResp)]
+    ListAuctions {
+        owner: Option<String>,
+        closed: Option<bool>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecMsg {
-    Bid {},
-    Close {},
+    /// Creates a new auction in this contract, with the sender as default owner
+    CreateAuction(Box<InstantiateMsg>),
+    Bid {
+        auction_id: u64,
+    },
+    /// Bids at least the buy-now price, net of commission, winning and closing the auction
+    BuyNow {
+        auction_id: u64,
+    },
+    /// Bids for units of a multi-unit auction, sending `quantity` times `unit_price`.
+    /// Replaces the bidder's previous bid, which is refunded.
+    BidUnits {
+        auction_id: u64,
+        quantity: u64,
+        unit_price: Uint128,
+    },
+    /// Commits to a sealed bid, escrowing the sent funds as deposit.
+    /// `hash` is sha256 of the bidder address, the amount and a salt.
+    CommitBid {
+        auction_id: u64,
+        hash: Binary,
+    },
+    /// Reveals a committed bid once the end time passed, refunding the rest of the deposit
+    RevealBid {
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    },
+    Close {
+        auction_id: u64,
+    },
     Retract {
+        auction_id: u64,
         receiver: Option<String>,
     },
+    /// Returns the bids of a closed auction to their bidders, a page at a time.
+    /// Each call continues after the last bidder refunded by the previous one.
+    RefundAll {
+        auction_id: u64,
+        limit: Option<u32>,
+    },
+    RevealReserve {
+        auction_id: u64,
+        amount: Uint128,
+        salt: String,
+    },
+    ProposeNewOwner {
+        auction_id: u64,
+        new_owner: String,
+    },
+    AcceptOwnership {
+        auction_id: u64,
+    },
+    CancelOwnershipTransfer {
+        auction_id: u64,
+    },
+    /// Closes the auction without a sale, letting every bidder retract
+    Cancel {
+        auction_id: u64,
+        reason: Option<String>,
+    },
+    /// Delivers the commodity to the winner of a closed auction
+    Claim {
+        auction_id: u64,
+    },
+    /// Bids in auctions priced in a CW20 token, with a `ReceiveMsg` hook
+    Receive(Cw20ReceiveMsg),
+    /// Opens an auction for the sent NFT, with the auction's `InstantiateMsg` as hook.
+    /// The NFT goes to the winner on close, or back to the owner without a sale.
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    Bid {
+        auction_id: u64,
+    },
+    BuyNow {
+        auction_id: u64,
+    },
+    CommitBid {
+        auction_id: u64,
+        hash: Binary,
+    },
+    BidUnits {
+        auction_id: u64,
+        quantity: u64,
+        unit_price: Uint128,
+    },
 }
 
 #[cw_serde]
@@ -49,12 +441,134 @@ pub struct HighestBidResp {
 }
 
 #[cw_serde]
-pub struct IsClosedResp {
-    pub closed: bool,
+pub struct StatusResp {
+    pub status: AuctionStatus,
 }
 
 #[cw_serde]
 pub struct WinnerResp {
     pub address: Addr,
     pub amount: Uint128,
+    /// Amount paid by the winner, below `amount` in second-price auctions
+    pub price: Uint128,
+    /// False when the highest bid did not reach the reserve price,
+    /// in which case nothing was sold
+    pub reserve_met: bool,
+}
+
+#[cw_serde]
+pub struct ConfigResp {
+    pub commodity_uri: String,
+    pub owner: Addr,
+    /// NFT held by the contract as the auctioned commodity
+    pub nft: Option<Nft>,
+    pub bid_comission: Decimal,
+    pub bid_denom: String,
+    pub cw20_addr: Option<Addr>,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
+    pub extension: Option<BidExtension>,
+    pub reserve_price: Option<ReservePrice>,
+    pub min_increment: Option<MinIncrement>,
+    pub buy_now_price: Option<Uint128>,
+    pub commission_policy: CommissionPolicy,
+    pub auction_type: AuctionType,
+}
+
+#[cw_serde]
+pub struct FeeShareResp {
+    pub address: Addr,
+    pub share: Decimal,
+}
+
+#[cw_serde]
+pub struct FeeConfigResp {
+    pub recipients: Vec<FeeShareResp>,
+    pub base: FeeBase,
+}
+
+#[cw_serde]
+pub struct EndTimeResp {
+    pub end_time: Option<Timestamp>,
+}
+
+#[cw_serde]
+pub struct MinNextBidResp {
+    /// Total bid, after commission, required to become the highest bidder
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct AllocationResp {
+    pub quantity: u64,
+    pub unit_price: Uint128,
+    pub allocated: u64,
+    /// Price paid for every allocated unit
+    pub clearing_price: Option<Uint128>,
+    /// Funds still held for the bidder, returned with `Retract`
+    pub refundable: Uint128,
+}
+
+#[cw_serde]
+pub struct CurrentPriceResp {
+    pub price: Uint128,
+}
+
+#[cw_serde]
+pub struct OwnershipResp {
+    pub owner: Addr,
+    pub pending_owner: Option<Addr>,
+}
+
+#[cw_serde]
+pub struct AuctionResp {
+    pub auction_id: u64,
+    pub commodity_uri: String,
+    pub owner: Addr,
+    pub bid_denom: String,
+    pub end_time: Option<Timestamp>,
+    pub status: AuctionStatus,
+}
+
+#[cw_serde]
+pub struct ListAuctionsResp {
+    pub auctions: Vec<AuctionResp>,
+}
+
+#[cw_serde]
+pub struct BidResp {
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+#[cw_serde]
+pub struct AllBidsResp {
+    pub bids: Vec<BidResp>,
+}
+
+#[cw_serde]
+pub struct TopBidsResp {
+    pub bids: Vec<BidResp>,
+}
+
+#[cw_serde]
+pub struct BidRecordResp {
+    pub seq: u64,
+    pub bidder: Addr,
+    pub amount: Uint128,
+    pub commission: Uint128,
+    pub net_amount: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[cw_serde]
+pub struct BidHistoryResp {
+    pub bids: Vec<BidRecordResp>,
+}
+
+#[cw_serde]
+pub struct SettlementResp {
+    pub status: SettlementStatus,
+    pub closed_at: Timestamp,
 }
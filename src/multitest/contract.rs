@@ -1,10 +1,17 @@
-use cosmwasm_std::{Addr, Coin, StdResult, Uint128, Decimal};
-use cw_multi_test::{App, ContractWrapper, Executor};
+use cosmwasm_std::{to_binary, Addr, Binary, Coin, StdResult, Uint128, Decimal};
+use cw20::Cw20ExecuteMsg;
+use cw721::Cw721ExecuteMsg;
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
 
 use crate::error::ContractError;
-use crate::msg::{ExecMsg, InstantiateMsg, QueryMsg, TotalUserBidResp, HighestBidResp, IsClosedResp, WinnerResp};
-use crate::{execute, instantiate, query};
+use crate::msg::{ExecMsg, InstantiateMsg, MigrateMsg, QueryMsg, ReceiveMsg, TotalUserBidResp, HighestBidResp, StatusResp, WinnerResp, ConfigResp, EndTimeResp, MinNextBidResp, OwnershipResp, ListAuctionsResp, AllBidsResp, BidOrder, TopBidsResp, BidHistoryResp, FeeConfigResp, SettlementResp, CurrentPriceResp, AllocationResp};
+use crate::{execute, instantiate, migrate, query};
 
+pub const BID_DENOM: &str = "uatom";
+// Auction created when instantiating the contract
+pub const AUCTION_ID: u64 = 1;
+
+#[derive(Debug)]
 pub struct BiddingContract(Addr);
 
 impl BiddingContract {
@@ -13,20 +20,40 @@ impl BiddingContract {
     }
 
     pub fn store_code(app: &mut App) -> u64 {
-        let contract = ContractWrapper::new(execute, instantiate, query);
+        let contract = ContractWrapper::new(execute, instantiate, query).with_migrate(migrate);
         app.store_code(Box::new(contract))
     }
 
-    pub fn get_default_contract(app: &mut App, code_id: u64, owner: &Addr) -> StdResult<Self> {
+    pub fn default_instantiate_msg() -> InstantiateMsg {
+        InstantiateMsg {
+            commodity_uri: "someuri".to_string(),
+            owner: None,
+            bid_comission: Decimal::from_atomics(Uint128::one(), 1).unwrap(),
+            bid_denom: BID_DENOM.to_string(),
+            cw20_addr: None,
+            start_time: None,
+            end_time: None,
+            extension: None,
+            reserve_price: None,
+            min_increment: None,
+            buy_now_price: None,
+            commission_policy: None,
+            fee_recipients: None,
+            fee_base: None,
+            claim_grace_period: None,
+            cancel_requires_no_bids: false,
+            auction_type: None,
+        }
+    }
+
+    pub fn get_default_contract(app: &mut App, code_id: u64, owner: &Addr) -> Result<Self, ContractError> {
         BiddingContract::instantiate(
             app,
             code_id,
             owner,
             "Bidding contract",
             None,
-            "someuri".to_string(),
-            Decimal::from_atomics(Uint128::one(), 1).unwrap(),
-            None,
+            BiddingContract::default_instantiate_msg(),
         )
     }
 
@@ -37,20 +64,14 @@ impl BiddingContract {
         sender: &Addr,
         label: &str,
         admin: impl Into<Option<&'a Addr>>,
-        commodity_uri: String,
-        bid_comission: Decimal,
-        owner: Option<String>,
-    ) -> StdResult<Self> {
+        msg: InstantiateMsg,
+    ) -> Result<Self, ContractError> {
         let admin = admin.into();
 
         app.instantiate_contract(
             code_id,
             sender.clone(),
-            &InstantiateMsg {
-                commodity_uri,
-                owner,
-                bid_comission,
-            },
+            &msg,
             &[],
             label,
             admin.map(Addr::to_string),
@@ -59,14 +80,147 @@ impl BiddingContract {
         .map_err(|err| err.downcast().unwrap())
     }
 
+    #[track_caller]
+    pub fn migrate(&self, app: &mut App, sender: &Addr, code_id: u64) -> Result<(), ContractError> {
+        app.migrate_contract(sender.clone(), self.0.clone(), &MigrateMsg {}, code_id)
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn create_auction(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        msg: InstantiateMsg,
+    ) -> Result<u64, ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::CreateAuction(Box::new(msg)), &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|resp| BiddingContract::auction_id(&resp))
+    }
+
+    #[track_caller]
+    pub fn create_nft_auction(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        nft_contract: &Addr,
+        token_id: &str,
+        msg: InstantiateMsg,
+    ) -> Result<u64, ContractError> {
+        let msg = Cw721ExecuteMsg::SendNft {
+            contract: self.0.to_string(),
+            token_id: token_id.to_string(),
+            msg: to_binary(&msg).unwrap(),
+        };
+        app.execute_contract(sender.clone(), nft_contract.clone(), &msg, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|resp| BiddingContract::auction_id(&resp))
+    }
+
+    fn auction_id(resp: &AppResponse) -> u64 {
+        resp.events
+            .iter()
+            .flat_map(|event| event.attributes.iter())
+            .find(|attr| attr.key == "auction_id")
+            .map(|attr| attr.value.parse().unwrap())
+            .unwrap()
+    }
+
     #[track_caller]
     pub fn bid(
         &self,
         app: &mut App,
         sender: &Addr,
+        auction_id: u64,
         funds: &[Coin],
     ) -> Result<(), ContractError> {
-        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Bid {}, funds)
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Bid { auction_id }, funds)
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn buy_now(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::BuyNow { auction_id }, funds)
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn commit_bid(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        hash: Binary,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::CommitBid { auction_id, hash }, funds)
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn bid_units(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        quantity: u64,
+        unit_price: u128,
+        funds: &[Coin],
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::BidUnits { auction_id, quantity, unit_price: Uint128::from(unit_price) },
+            funds,
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn reveal_bid(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        amount: u128,
+        salt: &str,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::RevealBid { auction_id, amount: Uint128::from(amount), salt: salt.to_string() },
+            &[],
+        )
+        .map_err(|err| err.downcast().unwrap())
+        .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn bid_cw20(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        token: &Addr,
+        auction_id: u64,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.0.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&ReceiveMsg::Bid { auction_id }).unwrap(),
+        };
+        app.execute_contract(sender.clone(), token.clone(), &msg, &[])
             .map_err(|err| err.downcast().unwrap())
             .map(|_| ())
     }
@@ -76,8 +230,34 @@ impl BiddingContract {
         &self,
         app: &mut App,
         sender: &Addr,
+        auction_id: u64,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Close { auction_id }, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn cancel(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        reason: Option<String>,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Cancel { auction_id, reason }, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn claim(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
     ) -> Result<(), ContractError> {
-        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Close {}, &[])
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Claim { auction_id }, &[])
             .map_err(|err| err.downcast().unwrap())
             .map(|_| ())
     }
@@ -87,35 +267,200 @@ impl BiddingContract {
         &self,
         app: &mut App,
         sender: &Addr,
+        auction_id: u64,
         receiver: Option<String>,
     ) -> Result<(), ContractError> {
-        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Retract { receiver }, &[])
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::Retract { auction_id, receiver }, &[])
             .map_err(|err| err.downcast().unwrap())
             .map(|_| ())
     }
 
     #[track_caller]
-    pub fn query_total_user_bid(&self, app: &App, address: String) -> StdResult<TotalUserBidResp> {
+    pub fn refund_all(&self, app: &mut App, sender: &Addr, auction_id: u64, limit: Option<u32>) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::RefundAll { auction_id, limit }, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn reveal_reserve(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        amount: Uint128,
+        salt: &str,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::RevealReserve { auction_id, amount, salt: salt.to_string() },
+            &[],
+        )
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn propose_new_owner(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+        new_owner: &Addr,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(
+            sender.clone(),
+            self.0.clone(),
+            &ExecMsg::ProposeNewOwner { auction_id, new_owner: new_owner.to_string() },
+            &[],
+        )
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn accept_ownership(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::AcceptOwnership { auction_id }, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn cancel_ownership_transfer(
+        &self,
+        app: &mut App,
+        sender: &Addr,
+        auction_id: u64,
+    ) -> Result<(), ContractError> {
+        app.execute_contract(sender.clone(), self.0.clone(), &ExecMsg::CancelOwnershipTransfer { auction_id }, &[])
+            .map_err(|err| err.downcast().unwrap())
+            .map(|_| ())
+    }
+
+    #[track_caller]
+    pub fn query_total_user_bid(&self, app: &App, auction_id: u64, address: String) -> StdResult<TotalUserBidResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::TotalUserBid { auction_id, address })
+    }
+
+    #[track_caller]
+    pub fn query_highest_bid_resp(&self, app: &App, auction_id: u64) -> StdResult<Option<HighestBidResp>> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::HighestBid { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_status(&self, app: &App, auction_id: u64) -> StdResult<StatusResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::TotalUserBid { address })
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Status { auction_id })
     }
 
     #[track_caller]
-    pub fn query_highest_bid_resp(&self, app: &App) -> StdResult<Option<HighestBidResp>> {
+    pub fn query_winner(&self, app: &App, auction_id: u64) -> StdResult<Option<WinnerResp>> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::HighestBid {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Winner { auction_id })
     }
 
     #[track_caller]
-    pub fn query_is_closed(&self, app: &App) -> StdResult<IsClosedResp> {
+    pub fn query_config(&self, app: &App, auction_id: u64) -> StdResult<ConfigResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::IsClosed {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Config { auction_id })
     }
 
     #[track_caller]
-    pub fn query_winner(&self, app: &App) -> StdResult<Option<WinnerResp>> {
+    pub fn query_end_time(&self, app: &App, auction_id: u64) -> StdResult<EndTimeResp> {
         app.wrap()
-            .query_wasm_smart(self.0.clone(), &QueryMsg::Winner {})
+            .query_wasm_smart(self.0.clone(), &QueryMsg::EndTime { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_min_next_bid(&self, app: &App, auction_id: u64) -> StdResult<MinNextBidResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::MinNextBid { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_current_price(&self, app: &App, auction_id: u64) -> StdResult<Option<CurrentPriceResp>> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::CurrentPrice { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_allocation(&self, app: &App, auction_id: u64, address: &Addr) -> StdResult<Option<AllocationResp>> {
+        app.wrap()
+            .query_wasm_smart(
+                self.0.clone(),
+                &QueryMsg::Allocation { auction_id, address: address.to_string() },
+            )
+    }
+
+    #[track_caller]
+    pub fn query_ownership(&self, app: &App, auction_id: u64) -> StdResult<OwnershipResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Ownership { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_all_bids(
+        &self,
+        app: &App,
+        auction_id: u64,
+        start_after: Option<String>,
+        limit: Option<u32>,
+        order_by: Option<BidOrder>,
+    ) -> StdResult<AllBidsResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::AllBids { auction_id, start_after, limit, order_by })
+    }
+
+    #[track_caller]
+    pub fn query_top_bids(&self, app: &App, auction_id: u64, limit: Option<u32>) -> StdResult<TopBidsResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::TopBids { auction_id, limit })
+    }
+
+    #[track_caller]
+    pub fn query_bid_history(
+        &self,
+        app: &App,
+        auction_id: u64,
+        bidder: Option<String>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<BidHistoryResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::BidHistory { auction_id, bidder, start_after, limit })
+    }
+
+    #[track_caller]
+    pub fn query_fee_config(&self, app: &App, auction_id: u64) -> StdResult<FeeConfigResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::FeeConfig { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_settlement(&self, app: &App, auction_id: u64) -> StdResult<Option<SettlementResp>> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::Settlement { auction_id })
+    }
+
+    #[track_caller]
+    pub fn query_list_auctions(
+        &self,
+        app: &App,
+        owner: Option<String>,
+        closed: Option<bool>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<ListAuctionsResp> {
+        app.wrap()
+            .query_wasm_smart(self.0.clone(), &QueryMsg::ListAuctions { owner, closed, start_after, limit })
     }
 }
 
@@ -124,3 +469,9 @@ impl From<BiddingContract> for Addr {
         contract.0
     }
 }
+
+impl From<Addr> for BiddingContract {
+    fn from(addr: Addr) -> Self {
+        BiddingContract(addr)
+    }
+}
@@ -1,9 +1,12 @@
-use cosmwasm_std::{Addr, Uint128, coins};
-use cw_multi_test::App;
+use cosmwasm_std::{Addr, Uint128, Decimal, coin, coins, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Response, StdResult, Storage};
+use cosmwasm_schema::cw_serde;
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg};
+use cw721::{Cw721QueryMsg, OwnerOfResponse};
+use cw_multi_test::{App, ContractWrapper, Executor};
 
-use crate::{msg::{TotalUserBidResp, HighestBidResp, IsClosedResp, WinnerResp}, state::BID_DENOM, error::ContractError};
+use crate::{contract::{bid_commitment, reserve_commitment, migration::{StateV0_2, BIDS_V0_1, BIDS_V0_3, PENDING_OWNER_V0_2, STATE_V0_2, WINNER_V0_1}}, state::Winner, msg::{TotalUserBidResp, HighestBidResp, StatusResp, AuctionStatus, AuctionType, SealedBid, DutchAuction, PriceDecay, DecayUnit, MultiUnitAuction, AllocationResp, CurrentPriceResp, WinnerResp, ConfigResp, InstantiateMsg, BidExtension, EndTimeResp, ReservePrice, MinIncrement, MinNextBidResp, OwnershipResp, AuctionResp, BidOrder, BidResp, BidRecordResp, CommissionPolicy, FeeRecipient, FeeBase, FeeConfigResp, FeeShareResp, Nft, SettlementResp, SettlementStatus}, error::ContractError};
 
-use super::contract::BiddingContract;
+use super::contract::{BiddingContract, AUCTION_ID, BID_DENOM};
 
 #[test]
 fn query_total_user_bid() {
@@ -22,10 +25,10 @@ fn query_total_user_bid() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    contract.bid(&mut app, &sender, &bid_amount1).unwrap();
-    contract.bid(&mut app, &sender, &bid_amount2).unwrap();
+    contract.bid(&mut app, &sender, AUCTION_ID, &bid_amount1).unwrap();
+    contract.bid(&mut app, &sender, AUCTION_ID, &bid_amount2).unwrap();
 
-    let resp = contract.query_total_user_bid(&app, sender.to_string()).unwrap();
+    let resp = contract.query_total_user_bid(&app, AUCTION_ID, sender.to_string()).unwrap();
     assert_eq!(resp, TotalUserBidResp { amount: Uint128::from(270u128) });
 }
 
@@ -49,15 +52,15 @@ fn query_highest_bid_resp() {
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
 
-    contract.bid(&mut app, &sender1, &bid_amount1).unwrap();
-    contract.bid(&mut app, &sender2, &bid_amount2).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &bid_amount1).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &bid_amount2).unwrap();
 
-    let resp = contract.query_highest_bid_resp(&app).unwrap();
+    let resp = contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap();
     assert_eq!(resp, Some(HighestBidResp {address: sender2, amount: Uint128::from(180u128)}));
 }
 
 #[test]
-fn query_is_closed() {
+fn query_status() {
     let owner = Addr::unchecked("owner");
     let sender1 = Addr::unchecked("alex");
     let bid_amount1 = coins(100u128, BID_DENOM);
@@ -71,14 +74,19 @@ fn query_is_closed() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    let resp = contract.query_is_closed(&app).unwrap();
-    assert_eq!(resp, IsClosedResp {closed: false});
+    let resp = contract.query_status(&app, AUCTION_ID).unwrap();
+    assert_eq!(resp, StatusResp { status: AuctionStatus::Active });
 
-    contract.bid(&mut app, &sender1, &bid_amount1).unwrap();
-    contract.close(&mut app, &owner).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &bid_amount1).unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
 
-    let resp = contract.query_is_closed(&app).unwrap();
-    assert_eq!(resp, IsClosedResp {closed: true});
+    let resp = contract.query_status(&app, AUCTION_ID).unwrap();
+    assert_eq!(resp, StatusResp { status: AuctionStatus::AwaitingSettlement });
+
+    contract.claim(&mut app, &sender1, AUCTION_ID).unwrap();
+
+    let resp = contract.query_status(&app, AUCTION_ID).unwrap();
+    assert_eq!(resp, StatusResp { status: AuctionStatus::Settled });
 }
 
 #[test]
@@ -100,18 +108,26 @@ fn query_winner() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    contract.bid(&mut app, &sender1, &bid_amount1).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &bid_amount1).unwrap();
     assert_eq!(
-        contract.query_highest_bid_resp(&app).unwrap(),
+        contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(),
         Some(HighestBidResp {address: sender1, amount: Uint128::from(90u128)}),
     );
-    assert_eq!(contract.query_winner(&app).unwrap(), None);
+    assert_eq!(contract.query_winner(&app, AUCTION_ID).unwrap(), None);
 
 
-    contract.bid(&mut app, &sender2, &bid_amount2).unwrap();
-    contract.close(&mut app, &owner).unwrap();
-    let resp = contract.query_winner(&app).unwrap();
-    assert_eq!(resp, Some(WinnerResp {address: sender2, amount: Uint128::from(180u128)}));
+    contract.bid(&mut app, &sender2, AUCTION_ID, &bid_amount2).unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    let resp = contract.query_winner(&app, AUCTION_ID).unwrap();
+    assert_eq!(
+        resp,
+        Some(WinnerResp {
+            address: sender2,
+            amount: Uint128::from(180u128),
+            price: Uint128::from(180u128),
+            reserve_met: true,
+        }),
+    );
 }
 
 #[test]
@@ -134,16 +150,16 @@ fn bid() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    let err = contract.bid(&mut app, &owner, &coins(10u128, BID_DENOM)).unwrap_err();
+    let err = contract.bid(&mut app, &owner, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap_err();
     assert_eq!(
         err,
         ContractError::Unauthorized {},
     );
 
-    contract.bid(&mut app, &sender1, &coins(10u128, BID_DENOM)).unwrap();
-    contract.bid(&mut app, &sender2, &coins(20u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
 
-    let err = contract.bid(&mut app, &sender1, &coins(10u128, BID_DENOM)).unwrap_err();
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap_err();
     assert_eq!(
         err,
         ContractError::InvalidBidAmount {amount: Uint128::from(9u128), required_amount: Uint128::from(10u128)},
@@ -170,13 +186,13 @@ fn close() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    contract.bid(&mut app, &sender1, &bid_amount1).unwrap();
-    contract.close(&mut app, &owner).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &bid_amount1).unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
 
-    let err = contract.bid(&mut app, &sender2, &bid_amount2).unwrap_err();
+    let err = contract.bid(&mut app, &sender2, AUCTION_ID, &bid_amount2).unwrap_err();
     assert_eq!(
         err,
-        ContractError::UnauthorizedWhileClosed {},
+        ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::AwaitingSettlement },
     );
 }
 
@@ -199,28 +215,2108 @@ fn retract() {
     let code_id = BiddingContract::store_code(&mut app);
     let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
 
-    contract.bid(&mut app, &sender1, &bid_amount1).unwrap();
-    contract.bid(&mut app, &sender2, &bid_amount2).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &bid_amount1).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &bid_amount2).unwrap();
 
-    let err = contract.retract(&mut app, &sender1, None).unwrap_err();
+    let err = contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap_err();
     assert_eq!(
         err,
-        ContractError::UnauthorizedWhileOpen {},
+        ContractError::InvalidTransition { action: "retract".to_string(), status: AuctionStatus::Active },
     );
 
-    contract.close(&mut app, &owner).unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
 
     assert_eq!(
         app.wrap().query_all_balances(&sender1).unwrap(),
         &[],
     );
-    contract.retract(&mut app, &sender1, None).unwrap();
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
     assert_eq!(
         app.wrap().query_all_balances(&sender1).unwrap(),
         coins(90, BID_DENOM),
     );
 
-    let err = contract.retract(&mut app, &sender2, None).unwrap_err();
+    let err = contract.retract(&mut app, &sender2, AUCTION_ID, None).unwrap_err();
     assert_eq!(err, ContractError::InvalidRetractZeroAmount {});
 
 }
+
+#[test]
+fn query_config() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    let resp = contract.query_config(&app, AUCTION_ID).unwrap();
+    assert_eq!(
+        resp,
+        ConfigResp {
+            commodity_uri: "someuri".to_string(),
+            nft: None,
+            owner,
+            bid_comission: Decimal::from_atomics(Uint128::one(), 1).unwrap(),
+            bid_denom: BID_DENOM.to_string(),
+            cw20_addr: None,
+            start_time: None,
+            end_time: None,
+            extension: None,
+            reserve_price: None,
+            min_increment: None,
+            buy_now_price: None,
+            commission_policy: CommissionPolicy::OnEveryBid,
+            auction_type: AuctionType::English,
+        },
+    );
+}
+
+#[test]
+fn bid_denom() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let ibc_denom = "ibc/27394FB092D2ECCD56123C74F36E4C1F926001CEADA9CA97EA622B25F41E5EB2";
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, vec![coin(100u128, BID_DENOM), coin(100u128, ibc_denom)])
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            bid_denom: ibc_denom.to_string(),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidZeroAmount {});
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, ibc_denom)).unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&owner, ibc_denom).unwrap().amount,
+        Uint128::from(10u128),
+    );
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&owner, ibc_denom).unwrap().amount,
+        Uint128::from(100u128),
+    );
+}
+
+#[test]
+fn bid_window() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let now = app.block_info().time;
+    let start_time = now.plus_seconds(100);
+    let end_time = now.plus_seconds(200);
+
+    let err = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            start_time: Some(end_time),
+            end_time: Some(start_time),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidWindow {});
+
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            start_time: Some(start_time),
+            end_time: Some(end_time),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::Scheduled });
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Scheduled });
+
+    app.update_block(|block| block.time = start_time);
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+
+    // Only the owner can close before the end time
+    let err = contract.close(&mut app, &sender2, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    app.update_block(|block| block.time = end_time);
+    let err = contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::Ended });
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Ended });
+
+    // Anyone can close after the end time
+    contract.close(&mut app, &sender2, AUCTION_ID).unwrap();
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender1,
+            amount: Uint128::from(9u128),
+            price: Uint128::from(9u128),
+            reserve_met: true,
+        }),
+    );
+    assert_eq!(
+        app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount,
+        Uint128::from(10u128),
+    );
+}
+
+#[test]
+fn bid_extension() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let now = app.block_info().time;
+    let end_time = now.plus_seconds(1000);
+
+    let err = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            extension: Some(BidExtension { window: 60, duration: 120 }),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidExtension {});
+
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            end_time: Some(end_time),
+            extension: Some(BidExtension { window: 60, duration: 120 }),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    // Bids outside the window do not extend the end time
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    assert_eq!(contract.query_end_time(&app, AUCTION_ID).unwrap(), EndTimeResp { end_time: Some(end_time) });
+
+    app.update_block(|block| block.time = end_time.minus_seconds(30));
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    let extended = end_time.plus_seconds(120);
+    assert_eq!(contract.query_end_time(&app, AUCTION_ID).unwrap(), EndTimeResp { end_time: Some(extended) });
+
+    // The original end time no longer ends the bid
+    app.update_block(|block| block.time = end_time.plus_seconds(70));
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(30u128, BID_DENOM)).unwrap();
+    assert_eq!(
+        contract.query_end_time(&app, AUCTION_ID).unwrap(),
+        EndTimeResp { end_time: Some(extended.plus_seconds(120)) },
+    );
+
+    app.update_block(|block| block.time = extended.plus_seconds(120));
+    let err = contract.bid(&mut app, &sender2, AUCTION_ID, &coins(40u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::Ended });
+}
+
+#[test]
+fn reserve_price_not_met() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            reserve_price: Some(ReservePrice::Public(Uint128::from(100u128))),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(50u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender2.clone(),
+            amount: Uint128::from(90u128),
+            price: Uint128::zero(),
+            reserve_met: false,
+        }),
+    );
+
+    // Only commissions were paid to the owner
+    assert_eq!(
+        app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount,
+        Uint128::from(15u128),
+    );
+
+    // The highest bidder can retract as well
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    contract.retract(&mut app, &sender2, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_all_balances(&sender1).unwrap(), coins(95, BID_DENOM));
+    assert_eq!(app.wrap().query_all_balances(&sender2).unwrap(), coins(90, BID_DENOM));
+}
+
+#[test]
+fn reserve_price_hidden() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let reserve = Uint128::from(80u128);
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            reserve_price: Some(ReservePrice::Hidden(reserve_commitment(reserve, "salt"))),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+
+    let err = contract.close(&mut app, &owner, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::ReserveNotRevealed {});
+
+    let err = contract.reveal_reserve(&mut app, &owner, AUCTION_ID, reserve, "pepper").unwrap_err();
+    assert_eq!(err, ContractError::InvalidReserveReveal {});
+
+    let err = contract.reveal_reserve(&mut app, &sender1, AUCTION_ID, reserve, "salt").unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    contract.reveal_reserve(&mut app, &owner, AUCTION_ID, reserve, "salt").unwrap();
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender1,
+            amount: Uint128::from(90u128),
+            price: Uint128::from(90u128),
+            reserve_met: true,
+        }),
+    );
+    assert_eq!(
+        app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount,
+        Uint128::from(100u128),
+    );
+}
+
+#[test]
+fn min_increment() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(1000u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(1000u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            min_increment: Some(MinIncrement::Absolute(Uint128::from(50u128))),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    assert_eq!(contract.query_min_next_bid(&app, AUCTION_ID).unwrap(), MinNextBidResp { amount: Uint128::from(50u128) });
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    assert_eq!(contract.query_min_next_bid(&app, AUCTION_ID).unwrap(), MinNextBidResp { amount: Uint128::from(140u128) });
+
+    let err = contract.bid(&mut app, &sender2, AUCTION_ID, &coins(150u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBidAmount { amount: Uint128::from(135u128), required_amount: Uint128::from(140u128) },
+    );
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(200u128, BID_DENOM)).unwrap();
+
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            min_increment: Some(MinIncrement::Percentage(Decimal::percent(10))),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    assert_eq!(contract.query_min_next_bid(&app, AUCTION_ID).unwrap(), MinNextBidResp { amount: Uint128::from(99u128) });
+
+    // Previous bids count towards the required amount
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(9u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBidAmount { amount: Uint128::from(8u128), required_amount: Uint128::from(9u128) },
+    );
+}
+
+#[test]
+fn ownership_transfer() {
+    let owner = Addr::unchecked("owner");
+    let new_owner = Addr::unchecked("multisig");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &new_owner, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+
+    let err = contract.propose_new_owner(&mut app, &sender1, AUCTION_ID, &new_owner).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = contract.propose_new_owner(&mut app, &owner, AUCTION_ID, &sender1).unwrap_err();
+    assert_eq!(err, ContractError::PendingOwnerIsBidder {});
+
+    let err = contract.accept_ownership(&mut app, &new_owner, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::NoPendingOwner {});
+
+    contract.propose_new_owner(&mut app, &owner, AUCTION_ID, &new_owner).unwrap();
+    assert_eq!(
+        contract.query_ownership(&app, AUCTION_ID).unwrap(),
+        OwnershipResp { owner: owner.clone(), pending_owner: Some(new_owner.clone()) },
+    );
+
+    let err = contract.bid(&mut app, &new_owner, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    let err = contract.accept_ownership(&mut app, &sender1, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    contract.cancel_ownership_transfer(&mut app, &owner, AUCTION_ID).unwrap();
+    assert_eq!(
+        contract.query_ownership(&app, AUCTION_ID).unwrap(),
+        OwnershipResp { owner: owner.clone(), pending_owner: None },
+    );
+
+    contract.propose_new_owner(&mut app, &owner, AUCTION_ID, &new_owner).unwrap();
+    contract.accept_ownership(&mut app, &new_owner, AUCTION_ID).unwrap();
+    assert_eq!(
+        contract.query_ownership(&app, AUCTION_ID).unwrap(),
+        OwnershipResp { owner: new_owner.clone(), pending_owner: None },
+    );
+
+    // Only the new owner can close
+    let err = contract.close(&mut app, &owner, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.close(&mut app, &new_owner, AUCTION_ID).unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&new_owner, BID_DENOM).unwrap().amount,
+        Uint128::from(109u128),
+    );
+}
+
+#[test]
+fn multiple_auctions() {
+    let owner1 = Addr::unchecked("owner");
+    let owner2 = Addr::unchecked("seller");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(200u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner1).unwrap();
+
+    let auction_id = contract.create_auction(
+        &mut app,
+        &owner2,
+        InstantiateMsg {
+            commodity_uri: "otheruri".to_string(),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+    assert_eq!(auction_id, AUCTION_ID + 1);
+
+    // Bids are tracked per auction
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender1, auction_id, &coins(50u128, BID_DENOM)).unwrap();
+    assert_eq!(
+        contract.query_total_user_bid(&app, auction_id, sender1.to_string()).unwrap(),
+        TotalUserBidResp { amount: Uint128::from(45u128) },
+    );
+
+    let err = contract.close(&mut app, &owner1, auction_id).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.close(&mut app, &owner2, auction_id).unwrap();
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Active });
+    assert_eq!(
+        app.wrap().query_balance(&owner2, BID_DENOM).unwrap().amount,
+        Uint128::from(50u128),
+    );
+
+    let first = AuctionResp {
+        auction_id: AUCTION_ID,
+        commodity_uri: "someuri".to_string(),
+        owner: owner1.clone(),
+        bid_denom: BID_DENOM.to_string(),
+        end_time: None,
+        status: AuctionStatus::Active,
+    };
+    let second = AuctionResp {
+        auction_id,
+        commodity_uri: "otheruri".to_string(),
+        owner: owner2.clone(),
+        bid_denom: BID_DENOM.to_string(),
+        end_time: None,
+        status: AuctionStatus::AwaitingSettlement,
+    };
+
+    let resp = contract.query_list_auctions(&app, None, None, None, None).unwrap();
+    assert_eq!(resp.auctions, vec![first.clone(), second.clone()]);
+
+    let resp = contract.query_list_auctions(&app, Some(owner2.to_string()), None, None, None).unwrap();
+    assert_eq!(resp.auctions, vec![second.clone()]);
+
+    let resp = contract.query_list_auctions(&app, None, Some(false), None, None).unwrap();
+    assert_eq!(resp.auctions, vec![first.clone()]);
+
+    let resp = contract.query_list_auctions(&app, None, None, None, Some(1)).unwrap();
+    assert_eq!(resp.auctions, vec![first]);
+
+    let resp = contract.query_list_auctions(&app, None, None, Some(AUCTION_ID), None).unwrap();
+    assert_eq!(resp.auctions, vec![second]);
+}
+
+#[test]
+fn query_all_bids() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender3, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender3, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(30u128, BID_DENOM)).unwrap();
+
+    let bid1 = BidResp { address: sender1, amount: Uint128::from(9u128) };
+    let bid2 = BidResp { address: sender2, amount: Uint128::from(27u128) };
+    let bid3 = BidResp { address: sender3, amount: Uint128::from(18u128) };
+
+    let resp = contract.query_all_bids(&app, AUCTION_ID, None, None, None).unwrap();
+    assert_eq!(resp.bids, vec![bid1.clone(), bid2.clone(), bid3.clone()]);
+
+    let resp = contract.query_all_bids(&app, AUCTION_ID, Some(bid1.address.to_string()), Some(1), None).unwrap();
+    assert_eq!(resp.bids, vec![bid2.clone()]);
+
+    let resp = contract.query_all_bids(&app, AUCTION_ID, None, None, Some(BidOrder::Amount)).unwrap();
+    assert_eq!(resp.bids, vec![bid2.clone(), bid3.clone(), bid1.clone()]);
+
+    let resp = contract.query_all_bids(&app, AUCTION_ID, None, Some(2), Some(BidOrder::Amount)).unwrap();
+    assert_eq!(resp.bids, vec![bid2, bid3.clone()]);
+    let resp = contract
+        .query_all_bids(&app, AUCTION_ID, Some(bid3.address.to_string()), Some(2), Some(BidOrder::Amount))
+        .unwrap();
+    assert_eq!(resp.bids, vec![bid1]);
+}
+
+#[test]
+fn query_top_bids() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender3, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender3, AUCTION_ID, &coins(30u128, BID_DENOM)).unwrap();
+    // Topping up moves the bidder up the ranking
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(30u128, BID_DENOM)).unwrap();
+
+    let resp = contract.query_top_bids(&app, AUCTION_ID, Some(2)).unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![
+            BidResp { address: sender1.clone(), amount: Uint128::from(36u128) },
+            BidResp { address: sender3.clone(), amount: Uint128::from(27u128) },
+        ],
+    );
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+
+    // The winner's bid was paid out and no longer ranks
+    let resp = contract.query_top_bids(&app, AUCTION_ID, None).unwrap();
+    assert_eq!(
+        resp.bids,
+        vec![
+            BidResp { address: sender3, amount: Uint128::from(27u128) },
+            BidResp { address: sender2, amount: Uint128::from(18u128) },
+        ],
+    );
+}
+
+#[test]
+fn query_bid_history() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+    let block = app.block_info();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(15u128, BID_DENOM)).unwrap();
+
+    let record1 = BidRecordResp {
+        seq: 1,
+        bidder: sender1.clone(),
+        amount: Uint128::from(10u128),
+        commission: Uint128::from(1u128),
+        net_amount: Uint128::from(9u128),
+        height: block.height,
+        time: block.time,
+    };
+    let record2 = BidRecordResp {
+        seq: 2,
+        bidder: sender2,
+        amount: Uint128::from(20u128),
+        commission: Uint128::from(2u128),
+        net_amount: Uint128::from(18u128),
+        height: block.height,
+        time: block.time,
+    };
+    let record3 = BidRecordResp {
+        seq: 3,
+        bidder: sender1.clone(),
+        amount: Uint128::from(15u128),
+        commission: Uint128::from(2u128),
+        net_amount: Uint128::from(13u128),
+        height: block.height,
+        time: block.time,
+    };
+
+    let resp = contract.query_bid_history(&app, AUCTION_ID, None, None, None).unwrap();
+    assert_eq!(resp.bids, vec![record1.clone(), record2.clone(), record3.clone()]);
+
+    let resp = contract.query_bid_history(&app, AUCTION_ID, None, Some(1), Some(1)).unwrap();
+    assert_eq!(resp.bids, vec![record2]);
+
+    let resp = contract.query_bid_history(&app, AUCTION_ID, Some(sender1.to_string()), None, None).unwrap();
+    assert_eq!(resp.bids, vec![record1, record3.clone()]);
+
+    let resp = contract.query_bid_history(&app, AUCTION_ID, Some(sender1.to_string()), Some(1), None).unwrap();
+    assert_eq!(resp.bids, vec![record3]);
+
+    // Other auctions keep their own log
+    let auction_id = contract.create_auction(&mut app, &owner, BiddingContract::default_instantiate_msg()).unwrap();
+    let resp = contract.query_bid_history(&app, auction_id, None, None, None).unwrap();
+    assert_eq!(resp.bids, vec![]);
+}
+
+#[test]
+fn commission_policy() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+
+    // Only the winner pays commission, out of the full bid
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            commission_policy: Some(CommissionPolicy::WinnerOnly),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::zero());
+    assert_eq!(
+        contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(),
+        Some(HighestBidResp { address: sender2.clone(), amount: Uint128::from(20u128) }),
+    );
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(20u128));
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(100u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(80u128));
+
+    // Commissions are held until close and returned to the losers
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            commission_policy: Some(CommissionPolicy::EscrowRefundLosers),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(10u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(20u128, BID_DENOM)).unwrap();
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(20u128));
+    assert_eq!(
+        contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(),
+        Some(HighestBidResp { address: sender2.clone(), amount: Uint128::from(18u128) }),
+    );
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(40u128));
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(100u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(60u128));
+}
+
+#[test]
+fn fee_recipients() {
+    let owner = Addr::unchecked("owner");
+    let platform = Addr::unchecked("platform");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(200u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let err = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            fee_recipients: Some(vec![
+                FeeRecipient { address: platform.to_string(), share: Decimal::percent(60) },
+                FeeRecipient { address: "treasury".to_string(), share: Decimal::percent(50) },
+            ]),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap_err();
+    assert_eq!(err, ContractError::InvalidFeeShares {});
+
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            fee_recipients: Some(vec![FeeRecipient { address: platform.to_string(), share: Decimal::percent(20) }]),
+            fee_base: Some(FeeBase::All),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    assert_eq!(
+        contract.query_fee_config(&app, AUCTION_ID).unwrap(),
+        FeeConfigResp {
+            recipients: vec![FeeShareResp { address: platform.clone(), share: Decimal::percent(20) }],
+            base: FeeBase::All,
+        },
+    );
+
+    // Commissions of 10 and 20 are split 20/80
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(200u128, BID_DENOM)).unwrap();
+    assert_eq!(app.wrap().query_balance(&platform, BID_DENOM).unwrap().amount, Uint128::from(6u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(24u128));
+
+    // So are the proceeds of 180
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    assert_eq!(app.wrap().query_balance(&platform, BID_DENOM).unwrap().amount, Uint128::from(42u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(168u128));
+}
+
+fn cw20_token(app: &mut App, owner: &Addr, initial_balances: Vec<Cw20Coin>) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    let msg = cw20_base::msg::InstantiateMsg {
+        name: "Bidding token".to_string(),
+        symbol: "BID".to_string(),
+        decimals: 6,
+        initial_balances,
+        mint: None,
+        marketing: None,
+    };
+    app.instantiate_contract(code_id, owner.clone(), &msg, &[], "Bidding token", None).unwrap()
+}
+
+fn cw20_balance(app: &App, token: &Addr, address: &Addr) -> Uint128 {
+    let resp: BalanceResponse = app.wrap()
+        .query_wasm_smart(token, &Cw20QueryMsg::Balance { address: address.to_string() })
+        .unwrap();
+    resp.balance
+}
+
+#[test]
+fn cw20_bid() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let token = cw20_token(
+        &mut app,
+        &owner,
+        vec![
+            Cw20Coin { address: sender1.to_string(), amount: Uint128::from(100u128) },
+            Cw20Coin { address: sender2.to_string(), amount: Uint128::from(200u128) },
+        ],
+    );
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            cw20_addr: Some(token.to_string()),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidToken {});
+
+    contract.bid_cw20(&mut app, &sender1, &token, AUCTION_ID, 100).unwrap();
+    contract.bid_cw20(&mut app, &sender2, &token, AUCTION_ID, 200).unwrap();
+    assert_eq!(
+        contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(),
+        Some(HighestBidResp { address: sender2.clone(), amount: Uint128::from(180u128) }),
+    );
+    assert_eq!(cw20_balance(&app, &token, &owner), Uint128::from(30u128));
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    assert_eq!(cw20_balance(&app, &token, &owner), Uint128::from(210u128));
+    assert_eq!(cw20_balance(&app, &token, &sender1), Uint128::from(90u128));
+    assert_eq!(cw20_balance(&app, &token, &sender2), Uint128::zero());
+}
+
+fn cw721_collection(app: &mut App, owner: &Addr, token_ids: &[&str]) -> Addr {
+    let code_id = app.store_code(Box::new(ContractWrapper::new(
+        cw721_base::entry::execute,
+        cw721_base::entry::instantiate,
+        cw721_base::entry::query,
+    )));
+    let msg = cw721_base::InstantiateMsg {
+        name: "Bidding collection".to_string(),
+        symbol: "BIDNFT".to_string(),
+        minter: owner.to_string(),
+    };
+    let collection = app.instantiate_contract(code_id, owner.clone(), &msg, &[], "Bidding collection", None).unwrap();
+
+    for token_id in token_ids {
+        let msg = cw721_base::ExecuteMsg::<cw721_base::Extension, Empty>::Mint(cw721_base::MintMsg {
+            token_id: token_id.to_string(),
+            owner: owner.to_string(),
+            token_uri: None,
+            extension: None,
+        });
+        app.execute_contract(owner.clone(), collection.clone(), &msg, &[]).unwrap();
+    }
+
+    collection
+}
+
+fn cw721_owner(app: &App, collection: &Addr, token_id: &str) -> String {
+    let resp: OwnerOfResponse = app.wrap()
+        .query_wasm_smart(collection, &Cw721QueryMsg::OwnerOf { token_id: token_id.to_string(), include_expired: None })
+        .unwrap();
+    resp.owner
+}
+
+#[test]
+fn nft_auction() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let collection = cw721_collection(&mut app, &owner, &["sold", "unsold"]);
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    let auction_id = contract
+        .create_nft_auction(&mut app, &owner, &collection, "sold", BiddingContract::default_instantiate_msg())
+        .unwrap();
+    assert_eq!(cw721_owner(&app, &collection, "sold"), contract.addr().to_string());
+
+    let resp = contract.query_config(&app, auction_id).unwrap();
+    assert_eq!(resp.owner, owner);
+    assert_eq!(resp.nft, Some(Nft { contract: collection.clone(), token_id: "sold".to_string() }));
+
+    contract.bid(&mut app, &sender1, auction_id, &coins(100u128, BID_DENOM)).unwrap();
+    contract.close(&mut app, &owner, auction_id).unwrap();
+    assert_eq!(cw721_owner(&app, &collection, "sold"), contract.addr().to_string());
+
+    contract.claim(&mut app, &sender1, auction_id).unwrap();
+    assert_eq!(cw721_owner(&app, &collection, "sold"), sender1.to_string());
+
+    // Without bids the NFT goes back to the owner
+    let auction_id = contract
+        .create_nft_auction(&mut app, &owner, &collection, "unsold", BiddingContract::default_instantiate_msg())
+        .unwrap();
+    contract.close(&mut app, &owner, auction_id).unwrap();
+    assert_eq!(cw721_owner(&app, &collection, "unsold"), owner.to_string());
+}
+
+#[test]
+fn claim() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            claim_grace_period: Some(100),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    let err = contract.claim(&mut app, &sender1, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "claim".to_string(), status: AuctionStatus::Active });
+    assert_eq!(contract.query_settlement(&app, AUCTION_ID).unwrap(), None);
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    let closed_at = app.block_info().time;
+    assert_eq!(
+        contract.query_settlement(&app, AUCTION_ID).unwrap(),
+        Some(SettlementResp { status: SettlementStatus::Pending, closed_at }),
+    );
+
+    // Only the winner can claim during the grace period
+    let err = contract.claim(&mut app, &sender2, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    app.update_block(|block| block.time = closed_at.plus_seconds(100));
+    contract.claim(&mut app, &sender2, AUCTION_ID).unwrap();
+    assert_eq!(
+        contract.query_settlement(&app, AUCTION_ID).unwrap(),
+        Some(SettlementResp { status: SettlementStatus::Delivered, closed_at }),
+    );
+
+    let err = contract.claim(&mut app, &sender1, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "claim".to_string(), status: AuctionStatus::Settled });
+
+    // Nothing to claim without a sale
+    let auction_id = contract.create_auction(&mut app, &owner, BiddingContract::default_instantiate_msg()).unwrap();
+    contract.close(&mut app, &owner, auction_id).unwrap();
+    assert_eq!(contract.query_status(&app, auction_id).unwrap(), StatusResp { status: AuctionStatus::Unsold });
+    assert_eq!(
+        contract.query_settlement(&app, auction_id).unwrap(),
+        Some(SettlementResp { status: SettlementStatus::Cancelled, closed_at: closed_at.plus_seconds(100) }),
+    );
+}
+
+#[test]
+fn cancel() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(200u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(200u128, BID_DENOM)).unwrap();
+
+    let err = contract.cancel(&mut app, &sender1, AUCTION_ID, None).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+
+    contract.cancel(&mut app, &owner, AUCTION_ID, Some("Item damaged".to_string())).unwrap();
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Cancelled });
+    assert_eq!(contract.query_winner(&app, AUCTION_ID).unwrap(), None);
+    assert_eq!(
+        contract.query_settlement(&app, AUCTION_ID).unwrap().map(|i| i.status),
+        Some(SettlementStatus::Cancelled),
+    );
+
+    let err = contract.close(&mut app, &owner, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "close".to_string(), status: AuctionStatus::Cancelled });
+
+    // The highest bidder gets their bid back as well
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    contract.retract(&mut app, &sender2, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(90u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(180u128));
+
+    let msg = InstantiateMsg {
+        cancel_requires_no_bids: true,
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let auction_id = contract.create_auction(&mut app, &owner, msg.clone()).unwrap();
+    contract.bid(&mut app, &sender1, auction_id, &coins(10u128, BID_DENOM)).unwrap();
+    let err = contract.cancel(&mut app, &owner, auction_id, None).unwrap_err();
+    assert_eq!(err, ContractError::CancelWithBids {});
+
+    let auction_id = contract.create_auction(&mut app, &owner, msg).unwrap();
+    contract.cancel(&mut app, &owner, auction_id, None).unwrap();
+}
+
+#[test]
+fn refund_all() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+    let sweeper = Addr::unchecked("carol");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(200u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender3, coins(300u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::get_default_contract(&mut app, code_id, &owner).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(200u128, BID_DENOM)).unwrap();
+    contract.bid(&mut app, &sender3, AUCTION_ID, &coins(300u128, BID_DENOM)).unwrap();
+
+    let err = contract.refund_all(&mut app, &sweeper, AUCTION_ID, None).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "refund_all".to_string(), status: AuctionStatus::Active });
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+
+    // Anyone can sweep, a page at a time
+    contract.refund_all(&mut app, &sweeper, AUCTION_ID, Some(1)).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(90u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::zero());
+
+    // The winner's bid was already paid out
+    contract.refund_all(&mut app, &sweeper, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(180u128));
+    assert_eq!(app.wrap().query_balance(&sender3, BID_DENOM).unwrap().amount, Uint128::zero());
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(330u128));
+
+    let err = contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap_err();
+    assert_eq!(err, ContractError::InvalidRetractZeroAmount {});
+
+    // Nothing left once drained
+    contract.refund_all(&mut app, &sweeper, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(90u128));
+}
+
+#[test]
+fn sealed_bid() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+
+    let mut app = App::new(|router, _api, storage| {
+        for sender in [&sender1, &sender2, &sender3] {
+            router.bank
+                .init_balance(storage, sender, coins(300u128, BID_DENOM))
+                .unwrap();
+        }
+    });
+    let end_time = app.block_info().time.plus_seconds(100);
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            end_time: Some(end_time),
+            auction_type: Some(AuctionType::Sealed(SealedBid {
+                min_deposit: Uint128::from(100u128),
+                reveal_period: 50,
+                penalty: Decimal::percent(50),
+            })),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::UnsupportedAction { action: "bid".to_string() });
+
+    // Without a reveal period every deposit would be forfeited
+    let msg = InstantiateMsg {
+        end_time: Some(end_time),
+        auction_type: Some(AuctionType::Sealed(SealedBid {
+            min_deposit: Uint128::from(100u128),
+            reveal_period: 0,
+            penalty: Decimal::percent(50),
+        })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidSealedAuction {});
+
+    let hash = bid_commitment(&sender1, Uint128::from(200u128), "alex salt");
+    let err = contract.commit_bid(&mut app, &sender1, AUCTION_ID, hash.clone(), &coins(50u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidDeposit { amount: Uint128::from(50u128), required_amount: Uint128::from(100u128) },
+    );
+
+    // Deposits hide the bid amounts
+    contract.commit_bid(&mut app, &sender1, AUCTION_ID, hash, &coins(300u128, BID_DENOM)).unwrap();
+    let hash = bid_commitment(&sender2, Uint128::from(250u128), "anna salt");
+    contract.commit_bid(&mut app, &sender2, AUCTION_ID, hash, &coins(300u128, BID_DENOM)).unwrap();
+    let hash = bid_commitment(&sender3, Uint128::from(100u128), "bob salt");
+    contract.commit_bid(&mut app, &sender3, AUCTION_ID, hash, &coins(300u128, BID_DENOM)).unwrap();
+    assert_eq!(contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(), None);
+
+    // Committed bidders cannot take over the auction they bid on
+    let err = contract.propose_new_owner(&mut app, &owner, AUCTION_ID, &sender1).unwrap_err();
+    assert_eq!(err, ContractError::PendingOwnerIsBidder {});
+
+    let err = contract.reveal_bid(&mut app, &sender1, AUCTION_ID, 200, "alex salt").unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "reveal_bid".to_string(), status: AuctionStatus::Active });
+
+    app.update_block(|block| block.time = end_time);
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Revealing });
+
+    let err = contract.reveal_bid(&mut app, &sender1, AUCTION_ID, 200, "pepper").unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidReveal {});
+
+    // The rest of the deposit is returned on reveal
+    contract.reveal_bid(&mut app, &sender1, AUCTION_ID, 200, "alex salt").unwrap();
+    contract.reveal_bid(&mut app, &sender2, AUCTION_ID, 250, "anna salt").unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(100u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(50u128));
+
+    let err = contract.close(&mut app, &owner, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::InvalidTransition { action: "close".to_string(), status: AuctionStatus::Revealing });
+
+    app.update_block(|block| block.time = end_time.plus_seconds(50));
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender2.clone(),
+            amount: Uint128::from(225u128),
+            price: Uint128::from(225u128),
+            reserve_met: true,
+        }),
+    );
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(270u128));
+
+    // Bob never revealed and loses half of his deposit
+    contract.refund_all(&mut app, &owner, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(280u128));
+    assert_eq!(app.wrap().query_balance(&sender3, BID_DENOM).unwrap().amount, Uint128::from(150u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(420u128));
+}
+
+#[test]
+fn sealed_bid_cancel() {
+    let owner = Addr::unchecked("owner");
+    let sender = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender, coins(600u128, BID_DENOM))
+            .unwrap();
+    });
+    let end_time = app.block_info().time.plus_seconds(100);
+    let msg = InstantiateMsg {
+        end_time: Some(end_time),
+        auction_type: Some(AuctionType::Sealed(SealedBid {
+            min_deposit: Uint128::from(100u128),
+            reveal_period: 50,
+            penalty: Decimal::percent(50),
+        })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        msg.clone(),
+    ).unwrap();
+
+    let hash = bid_commitment(&sender, Uint128::from(200u128), "alex salt");
+    contract.commit_bid(&mut app, &sender, AUCTION_ID, hash.clone(), &coins(300u128, BID_DENOM)).unwrap();
+    contract.cancel(&mut app, &owner, AUCTION_ID, None).unwrap();
+
+    // The deposit never had the chance to be revealed, so no penalty applies
+    contract.retract(&mut app, &sender, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender, BID_DENOM).unwrap().amount, Uint128::from(600u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::zero());
+
+    // Committed bids count as bids
+    let msg = InstantiateMsg { cancel_requires_no_bids: true, ..msg };
+    let auction_id = contract.create_auction(&mut app, &owner, msg).unwrap();
+    contract.commit_bid(&mut app, &sender, auction_id, hash, &coins(300u128, BID_DENOM)).unwrap();
+    let err = contract.cancel(&mut app, &owner, auction_id, None).unwrap_err();
+    assert_eq!(err, ContractError::CancelWithBids {});
+}
+
+#[test]
+fn vickrey() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+    let platform = Addr::unchecked("platform");
+
+    let mut app = App::new(|router, _api, storage| {
+        for sender in [&sender1, &sender2, &sender3] {
+            router.bank
+                .init_balance(storage, sender, coins(300u128, BID_DENOM))
+                .unwrap();
+        }
+    });
+    let end_time = app.block_info().time.plus_seconds(100);
+    let msg = InstantiateMsg {
+        end_time: Some(end_time),
+        reserve_price: Some(ReservePrice::Public(Uint128::from(150u128))),
+        fee_recipients: Some(vec![FeeRecipient { address: platform.to_string(), share: Decimal::one() }]),
+        auction_type: Some(AuctionType::Vickrey(SealedBid {
+            min_deposit: Uint128::from(100u128),
+            reveal_period: 50,
+            penalty: Decimal::zero(),
+        })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        msg.clone(),
+    ).unwrap();
+
+    // Commission is only charged on the second price, known at close
+    for commission_policy in [CommissionPolicy::OnEveryBid, CommissionPolicy::EscrowRefundLosers] {
+        let msg = InstantiateMsg { commission_policy: Some(commission_policy), ..msg.clone() };
+        let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSealedAuction {});
+    }
+    assert_eq!(contract.query_config(&app, AUCTION_ID).unwrap().commission_policy, CommissionPolicy::WinnerOnly);
+
+    let sealed_bids = [(&sender1, 200u128, "alex salt"), (&sender2, 250, "anna salt"), (&sender3, 100, "bob salt")];
+    for (sender, amount, salt) in sealed_bids {
+        let hash = bid_commitment(sender, Uint128::from(amount), salt);
+        contract.commit_bid(&mut app, sender, AUCTION_ID, hash, &coins(300u128, BID_DENOM)).unwrap();
+    }
+
+    app.update_block(|block| block.time = end_time);
+    contract.reveal_bid(&mut app, &sender1, AUCTION_ID, 200, "alex salt").unwrap();
+    contract.reveal_bid(&mut app, &sender2, AUCTION_ID, 250, "anna salt").unwrap();
+    contract.reveal_bid(&mut app, &sender3, AUCTION_ID, 100, "bob salt").unwrap();
+
+    app.update_block(|block| block.time = end_time.plus_seconds(50));
+    contract.close(&mut app, &sender3, AUCTION_ID).unwrap();
+
+    // The winner pays the second highest bid, commission being charged on that price
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender2.clone(),
+            amount: Uint128::from(250u128),
+            price: Uint128::from(200u128),
+            reserve_met: true,
+        }),
+    );
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(180u128));
+    assert_eq!(app.wrap().query_balance(&platform, BID_DENOM).unwrap().amount, Uint128::from(20u128));
+
+    // The rest of the winning bid is retracted like any other bid
+    contract.retract(&mut app, &sender2, AUCTION_ID, None).unwrap();
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(100u128));
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(300u128));
+}
+
+#[test]
+fn dutch() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(1000u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(1000u128, BID_DENOM))
+            .unwrap();
+    });
+    let start_time = app.block_info().time;
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            auction_type: Some(AuctionType::Dutch(DutchAuction {
+                start_price: Uint128::from(1000u128),
+                floor_price: Uint128::from(400u128),
+                decay: PriceDecay::Stepwise { amount: Uint128::from(100u128), interval: 10, unit: DecayUnit::Seconds },
+            })),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    assert_eq!(
+        contract.query_current_price(&app, AUCTION_ID).unwrap(),
+        Some(CurrentPriceResp { price: Uint128::from(1000u128) }),
+    );
+
+    app.update_block(|block| block.time = start_time.plus_seconds(25));
+    assert_eq!(
+        contract.query_current_price(&app, AUCTION_ID).unwrap(),
+        Some(CurrentPriceResp { price: Uint128::from(800u128) }),
+    );
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(700u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBidAmount { amount: Uint128::from(700u128), required_amount: Uint128::from(800u128) },
+    );
+
+    // The first bid meeting the price wins, and gets the rest back
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(900u128, BID_DENOM)).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(200u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(800u128));
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::AwaitingSettlement });
+    assert_eq!(contract.query_current_price(&app, AUCTION_ID).unwrap(), None);
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender1.clone(),
+            amount: Uint128::from(800u128),
+            price: Uint128::from(800u128),
+            reserve_met: true,
+        }),
+    );
+
+    let err = contract.bid(&mut app, &sender2, AUCTION_ID, &coins(1000u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::AwaitingSettlement },
+    );
+
+    // Linear decay per block, down to the floor
+    let msg = InstantiateMsg {
+        auction_type: Some(AuctionType::Dutch(DutchAuction {
+            start_price: Uint128::from(1000u128),
+            floor_price: Uint128::from(500u128),
+            decay: PriceDecay::Linear { duration: 10, unit: DecayUnit::Blocks },
+        })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let auction_id = contract.create_auction(&mut app, &owner, msg).unwrap();
+
+    app.update_block(|block| block.height += 4);
+    assert_eq!(
+        contract.query_current_price(&app, auction_id).unwrap(),
+        Some(CurrentPriceResp { price: Uint128::from(800u128) }),
+    );
+
+    app.update_block(|block| block.height += 20);
+    assert_eq!(
+        contract.query_current_price(&app, auction_id).unwrap(),
+        Some(CurrentPriceResp { price: Uint128::from(500u128) }),
+    );
+
+    // Block decay would already have started by a later opening
+    let dutch = DutchAuction {
+        start_price: Uint128::from(1000u128),
+        floor_price: Uint128::from(500u128),
+        decay: PriceDecay::Linear { duration: 10, unit: DecayUnit::Blocks },
+    };
+    let msg = InstantiateMsg {
+        start_time: Some(app.block_info().time.plus_seconds(100)),
+        auction_type: Some(AuctionType::Dutch(dutch.clone())),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidDutchAuction {});
+
+    // Nor can the price decay below the reserve
+    let msg = InstantiateMsg {
+        reserve_price: Some(ReservePrice::Public(Uint128::from(600u128))),
+        auction_type: Some(AuctionType::Dutch(dutch)),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidDutchAuction {});
+}
+
+#[test]
+fn multi_unit() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+    let sender3 = Addr::unchecked("bob");
+    let sender4 = Addr::unchecked("carl");
+    let platform = Addr::unchecked("platform");
+
+    let mut app = App::new(|router, _api, storage| {
+        for sender in [&sender1, &sender2, &sender3, &sender4] {
+            router.bank
+                .init_balance(storage, sender, coins(300u128, BID_DENOM))
+                .unwrap();
+        }
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            reserve_price: Some(ReservePrice::Public(Uint128::from(25u128))),
+            fee_recipients: Some(vec![FeeRecipient { address: platform.to_string(), share: Decimal::percent(50) }]),
+            auction_type: Some(AuctionType::MultiUnit(MultiUnitAuction { units: 10 })),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::UnsupportedAction { action: "bid".to_string() });
+
+    // Unit bids are neither extended, stepped up nor charged commission
+    let msg = InstantiateMsg {
+        auction_type: Some(AuctionType::MultiUnit(MultiUnitAuction { units: 10 })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let unsupported = [
+        InstantiateMsg {
+            end_time: Some(app.block_info().time.plus_seconds(100)),
+            extension: Some(BidExtension { window: 10, duration: 10 }),
+            ..msg.clone()
+        },
+        InstantiateMsg { min_increment: Some(MinIncrement::Absolute(Uint128::from(5u128))), ..msg.clone() },
+        InstantiateMsg { commission_policy: Some(CommissionPolicy::EscrowRefundLosers), ..msg.clone() },
+    ];
+    for msg in unsupported {
+        let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+        assert_eq!(err, ContractError::InvalidMultiUnitAuction {});
+    }
+    assert_eq!(contract.query_config(&app, AUCTION_ID).unwrap().commission_policy, CommissionPolicy::WinnerOnly);
+
+    let err = contract.bid_units(&mut app, &sender1, AUCTION_ID, 11, 10, &coins(110u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBidQuantity { max: 10 });
+
+    let err = contract.bid_units(&mut app, &sender1, AUCTION_ID, 4, 50, &coins(150u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBidAmount { amount: Uint128::from(150u128), required_amount: Uint128::from(200u128) },
+    );
+
+    // A new bid replaces the previous one, which is refunded
+    contract.bid_units(&mut app, &sender1, AUCTION_ID, 2, 50, &coins(100u128, BID_DENOM)).unwrap();
+    contract.bid_units(&mut app, &sender1, AUCTION_ID, 4, 50, &coins(200u128, BID_DENOM)).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(100u128));
+
+    contract.bid_units(&mut app, &sender2, AUCTION_ID, 5, 40, &coins(200u128, BID_DENOM)).unwrap();
+    contract.bid_units(&mut app, &sender3, AUCTION_ID, 3, 30, &coins(90u128, BID_DENOM)).unwrap();
+    contract.bid_units(&mut app, &sender4, AUCTION_ID, 2, 20, &coins(40u128, BID_DENOM)).unwrap();
+
+    contract.close(&mut app, &owner, AUCTION_ID).unwrap();
+
+    // 10 units sold at the lowest accepted price, 30, the platform taking half the commission
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::AwaitingSettlement });
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(285u128));
+    assert_eq!(app.wrap().query_balance(&platform, BID_DENOM).unwrap().amount, Uint128::from(15u128));
+
+    // The marginal bidder only gets the units left
+    assert_eq!(
+        contract.query_allocation(&app, AUCTION_ID, &sender3).unwrap(),
+        Some(AllocationResp {
+            quantity: 3,
+            unit_price: Uint128::from(30u128),
+            allocated: 1,
+            clearing_price: Some(Uint128::from(30u128)),
+            refundable: Uint128::from(60u128),
+        }),
+    );
+    assert_eq!(
+        contract.query_allocation(&app, AUCTION_ID, &sender1).unwrap(),
+        Some(AllocationResp {
+            quantity: 4,
+            unit_price: Uint128::from(50u128),
+            allocated: 4,
+            clearing_price: Some(Uint128::from(30u128)),
+            refundable: Uint128::from(80u128),
+        }),
+    );
+    assert_eq!(
+        contract.query_allocation(&app, AUCTION_ID, &sender4).unwrap(),
+        Some(AllocationResp {
+            quantity: 2,
+            unit_price: Uint128::from(20u128),
+            allocated: 0,
+            clearing_price: Some(Uint128::from(30u128)),
+            refundable: Uint128::from(40u128),
+        }),
+    );
+    assert_eq!(contract.query_allocation(&app, AUCTION_ID, &owner).unwrap(), None);
+
+    // Winners and losers retract whatever they bid above their allocation
+    for sender in [&sender1, &sender2, &sender3, &sender4] {
+        contract.retract(&mut app, sender, AUCTION_ID, None).unwrap();
+    }
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(180u128));
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(150u128));
+    assert_eq!(app.wrap().query_balance(&sender3, BID_DENOM).unwrap().amount, Uint128::from(270u128));
+    assert_eq!(app.wrap().query_balance(&sender4, BID_DENOM).unwrap().amount, Uint128::from(300u128));
+
+    // Only bidders with an allocation can claim
+    let err = contract.claim(&mut app, &sender4, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::Unauthorized {});
+    contract.claim(&mut app, &sender3, AUCTION_ID).unwrap();
+    let err = contract.claim(&mut app, &sender3, AUCTION_ID).unwrap_err();
+    assert_eq!(err, ContractError::AlreadyClaimed {});
+
+    // The lot is settled once every winner claimed their units
+    contract.claim(&mut app, &sender1, AUCTION_ID).unwrap();
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::AwaitingSettlement });
+    contract.claim(&mut app, &sender2, AUCTION_ID).unwrap();
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Settled });
+
+    // Unit bids count as bids
+    let msg = InstantiateMsg {
+        cancel_requires_no_bids: true,
+        auction_type: Some(AuctionType::MultiUnit(MultiUnitAuction { units: 10 })),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let auction_id = contract.create_auction(&mut app, &owner, msg).unwrap();
+    contract.bid_units(&mut app, &sender4, auction_id, 2, 20, &coins(40u128, BID_DENOM)).unwrap();
+    let err = contract.cancel(&mut app, &owner, auction_id, None).unwrap_err();
+    assert_eq!(err, ContractError::CancelWithBids {});
+}
+
+#[test]
+fn buy_now() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(1000u128, BID_DENOM))
+            .unwrap();
+        router.bank
+            .init_balance(storage, &sender2, coins(1000u128, BID_DENOM))
+            .unwrap();
+    });
+    let code_id = BiddingContract::store_code(&mut app);
+    let contract = BiddingContract::instantiate(
+        &mut app,
+        code_id,
+        &owner,
+        "Bidding contract",
+        None,
+        InstantiateMsg {
+            buy_now_price: Some(Uint128::from(500u128)),
+            ..BiddingContract::default_instantiate_msg()
+        },
+    ).unwrap();
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, BID_DENOM)).unwrap();
+
+    // Buying requires reaching the price, net of commission
+    let err = contract.buy_now(&mut app, &sender2, AUCTION_ID, &coins(400u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidBidAmount { amount: Uint128::from(360u128), required_amount: Uint128::from(500u128) },
+    );
+
+    // Any bid reaching the price wins right away. Commission is only charged on the 556 reaching it,
+    // the rest is returned in full.
+    contract.bid(&mut app, &sender2, AUCTION_ID, &coins(600u128, BID_DENOM)).unwrap();
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::AwaitingSettlement });
+    assert_eq!(
+        contract.query_winner(&app, AUCTION_ID).unwrap(),
+        Some(WinnerResp {
+            address: sender2.clone(),
+            amount: Uint128::from(500u128),
+            price: Uint128::from(500u128),
+            reserve_met: true,
+        }),
+    );
+    assert_eq!(app.wrap().query_balance(&sender2, BID_DENOM).unwrap().amount, Uint128::from(444u128));
+    assert_eq!(app.wrap().query_balance(&owner, BID_DENOM).unwrap().amount, Uint128::from(566u128));
+
+    let err = contract.bid(&mut app, &sender1, AUCTION_ID, &coins(700u128, BID_DENOM)).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidTransition { action: "bid".to_string(), status: AuctionStatus::AwaitingSettlement },
+    );
+    contract.retract(&mut app, &sender1, AUCTION_ID, None).unwrap();
+    assert_eq!(app.wrap().query_balance(&sender1, BID_DENOM).unwrap().amount, Uint128::from(990u128));
+
+    // Without a buy-now price there is nothing to buy
+    let auction_id = contract.create_auction(&mut app, &owner, BiddingContract::default_instantiate_msg()).unwrap();
+    let err = contract.buy_now(&mut app, &sender1, auction_id, &coins(100u128, BID_DENOM)).unwrap_err();
+    assert_eq!(err, ContractError::UnsupportedAction { action: "buy_now".to_string() });
+
+    let msg = InstantiateMsg {
+        buy_now_price: Some(Uint128::from(100u128)),
+        reserve_price: Some(ReservePrice::Public(Uint128::from(200u128))),
+        ..BiddingContract::default_instantiate_msg()
+    };
+    let err = contract.create_auction(&mut app, &owner, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidBuyNowPrice {});
+}
+
+// Stand-in for previously deployed code, storing a given cw2 version and the state of an older layout
+mod legacy {
+    use cw_storage_plus::{Item, Map};
+
+    use crate::state::{AUCTION_COUNT, SETTLEMENT, WINNER, Bid, Settlement, bids};
+
+    use super::*;
+
+    // State layout up to version 0.1.0
+    #[cw_serde]
+    pub struct StateV0_1 {
+        pub commodity_uri: String,
+        pub owner: Addr,
+        pub bid_comission: Decimal,
+        pub is_closed: bool,
+    }
+
+    pub const STATE_V0_1: Item<StateV0_1> = Item::new("state");
+
+    // Registry auction layout with status flags, from 0.3.0 until 0.5.0
+    #[cw_serde]
+    pub struct AuctionV0_4 {
+        pub commodity_uri: String,
+        pub owner: Addr,
+        pub bid_comission: Decimal,
+        pub bid_denom: String,
+        pub is_closed: bool,
+        pub is_cancelled: bool,
+        pub reserve_not_met: bool,
+    }
+
+    pub const AUCTIONS_V0_4: Map<u64, AuctionV0_4> = Map::new("auctions");
+
+    fn save_auction(storage: &mut dyn Storage, auction_id: u64, owner: &Addr, flags: (bool, bool, bool)) -> StdResult<()> {
+        let (is_closed, is_cancelled, reserve_not_met) = flags;
+        let auction = AuctionV0_4 {
+            commodity_uri: "someuri".to_string(),
+            owner: owner.clone(),
+            bid_comission: Decimal::percent(10),
+            bid_denom: BID_DENOM.to_string(),
+            is_closed,
+            is_cancelled,
+            reserve_not_met,
+        };
+        AUCTIONS_V0_4.save(storage, auction_id, &auction)?;
+        AUCTION_COUNT.save(storage, &auction_id)
+    }
+
+    #[cw_serde]
+    pub enum Layout {
+        /// Single open auction of 0.1.0
+        SingleV0_1,
+        /// Single auction of 0.2.0, closed below its reserve price
+        SingleV0_2,
+        /// Registry of 0.3.0 with plain bid amounts, an open auction and a sold one
+        RegistryV0_3,
+        /// Registry of 0.4.0 with closed auctions in every combination of status flags and settlement
+        RegistryV0_4,
+    }
+
+    #[cw_serde]
+    pub struct InstantiateMsg {
+        pub contract: String,
+        pub version: String,
+        pub layout: Layout,
+    }
+
+    pub fn instantiate(deps: DepsMut, env: Env, info: MessageInfo, msg: InstantiateMsg) -> StdResult<Response> {
+        cw2::set_contract_version(deps.storage, msg.contract, msg.version)?;
+        let bidder = Addr::unchecked("alex");
+
+        match msg.layout {
+            Layout::SingleV0_1 => {
+                STATE_V0_1.save(
+                    deps.storage,
+                    &StateV0_1 {
+                        commodity_uri: "someuri".to_string(),
+                        owner: info.sender,
+                        bid_comission: Decimal::percent(10),
+                        is_closed: false,
+                    },
+                )?;
+                BIDS_V0_1.save(deps.storage, &bidder, &Uint128::from(90u128))?;
+                WINNER_V0_1.save(deps.storage, &Winner { address: bidder, amount: Uint128::from(90u128) })?;
+            },
+            Layout::SingleV0_2 => {
+                STATE_V0_2.save(
+                    deps.storage,
+                    &StateV0_2 {
+                        commodity_uri: "someuri".to_string(),
+                        owner: info.sender,
+                        bid_comission: Decimal::percent(10),
+                        bid_denom: BID_DENOM.to_string(),
+                        start_time: None,
+                        end_time: Some(env.block.time.plus_seconds(100)),
+                        extension: None,
+                        reserve_price: Some(ReservePrice::Public(Uint128::from(100u128))),
+                        min_increment: None,
+                        is_closed: true,
+                        reserve_not_met: true,
+                    },
+                )?;
+                PENDING_OWNER_V0_2.save(deps.storage, &Addr::unchecked("anna"))?;
+                BIDS_V0_1.save(deps.storage, &bidder, &Uint128::from(90u128))?;
+                WINNER_V0_1.save(deps.storage, &Winner { address: bidder, amount: Uint128::from(90u128) })?;
+            },
+            Layout::RegistryV0_3 => {
+                save_auction(deps.storage, 1, &info.sender, (false, false, false))?;
+                BIDS_V0_3.save(deps.storage, (1, &bidder), &Uint128::from(90u128))?;
+                BIDS_V0_3.save(deps.storage, (1, &Addr::unchecked("anna")), &Uint128::from(50u128))?;
+                WINNER.save(deps.storage, 1, &Winner { address: bidder.clone(), amount: Uint128::from(90u128) })?;
+
+                save_auction(deps.storage, 2, &info.sender, (true, false, false))?;
+                WINNER.save(deps.storage, 2, &Winner { address: bidder, amount: Uint128::from(40u128) })?;
+            },
+            Layout::RegistryV0_4 => {
+                let winner = Winner { address: bidder.clone(), amount: Uint128::from(90u128) };
+                let settlement = |status| Settlement { status, closed_at: env.block.time, price: None };
+
+                // Cancelled, with the bid left to retract
+                save_auction(deps.storage, 1, &info.sender, (true, true, false))?;
+                let bid = Bid { amount: Uint128::from(90u128), ..Bid::empty(1) };
+                bids().save(deps.storage, (1, &bidder), &bid)?;
+                WINNER.save(deps.storage, 1, &winner)?;
+                SETTLEMENT.save(deps.storage, 1, &settlement(SettlementStatus::Cancelled))?;
+
+                // Closed below the reserve price
+                save_auction(deps.storage, 2, &info.sender, (true, false, true))?;
+                WINNER.save(deps.storage, 2, &winner)?;
+                SETTLEMENT.save(deps.storage, 2, &settlement(SettlementStatus::Cancelled))?;
+
+                // Sold, waiting to be claimed
+                save_auction(deps.storage, 3, &info.sender, (true, false, false))?;
+                WINNER.save(deps.storage, 3, &winner)?;
+                SETTLEMENT.save(deps.storage, 3, &settlement(SettlementStatus::Pending))?;
+
+                // Sold and delivered
+                save_auction(deps.storage, 4, &info.sender, (true, false, false))?;
+                WINNER.save(deps.storage, 4, &winner)?;
+                SETTLEMENT.save(deps.storage, 4, &settlement(SettlementStatus::Delivered))?;
+
+                // Closed without bids
+                save_auction(deps.storage, 5, &info.sender, (true, false, false))?;
+                SETTLEMENT.save(deps.storage, 5, &settlement(SettlementStatus::Cancelled))?;
+
+                // Closed before settlements were tracked, with and without a winner
+                save_auction(deps.storage, 6, &info.sender, (true, false, false))?;
+                WINNER.save(deps.storage, 6, &winner)?;
+                save_auction(deps.storage, 7, &info.sender, (true, false, false))?;
+
+                save_auction(deps.storage, 8, &info.sender, (false, false, false))?;
+            },
+        }
+
+        Ok(Response::new())
+    }
+
+    pub fn execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Ok(Binary::default())
+    }
+
+    pub fn store_code(app: &mut App) -> u64 {
+        let contract = ContractWrapper::new(execute, instantiate, query);
+        app.store_code(Box::new(contract))
+    }
+
+    pub fn instantiate_contract(app: &mut App, code_id: u64, owner: &Addr, contract: &str, version: &str) -> BiddingContract {
+        instantiate_layout(app, code_id, owner, contract, version, Layout::SingleV0_1)
+    }
+
+    pub fn instantiate_layout(
+        app: &mut App,
+        code_id: u64,
+        owner: &Addr,
+        contract: &str,
+        version: &str,
+        layout: Layout,
+    ) -> BiddingContract {
+        app.instantiate_contract(
+            code_id,
+            owner.clone(),
+            &InstantiateMsg { contract: contract.to_string(), version: version.to_string(), layout },
+            &[],
+            "Legacy bidding contract",
+            Some(owner.to_string()),
+        )
+        .unwrap()
+        .into()
+    }
+}
+
+#[test]
+fn migrate() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::new(|router, _api, storage| {
+        router.bank
+            .init_balance(storage, &sender1, coins(100u128, "ATOM"))
+            .unwrap();
+    });
+    let legacy_code_id = legacy::store_code(&mut app);
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = legacy::instantiate_contract(&mut app, legacy_code_id, &owner, "cw-bidding-platform", "0.1.0");
+    contract.migrate(&mut app, &owner, code_id).unwrap();
+
+    let resp = contract.query_config(&app, AUCTION_ID).unwrap();
+    assert_eq!(resp.owner, owner);
+    assert_eq!(resp.commodity_uri, "someuri".to_string());
+    assert_eq!(resp.bid_denom, "ATOM".to_string());
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Active });
+
+    assert_eq!(
+        contract.query_highest_bid_resp(&app, AUCTION_ID).unwrap(),
+        Some(HighestBidResp { address: sender1.clone(), amount: Uint128::from(90u128) }),
+    );
+    assert_eq!(
+        contract.query_top_bids(&app, AUCTION_ID, None).unwrap().bids,
+        vec![BidResp { address: sender1.clone(), amount: Uint128::from(90u128) }],
+    );
+
+    contract.bid(&mut app, &sender1, AUCTION_ID, &coins(100u128, "ATOM")).unwrap();
+    assert_eq!(
+        contract.query_total_user_bid(&app, AUCTION_ID, sender1.to_string()).unwrap(),
+        TotalUserBidResp { amount: Uint128::from(180u128) },
+    );
+
+    // Migrating to the same version is allowed
+    contract.migrate(&mut app, &owner, code_id).unwrap();
+}
+
+#[test]
+fn migrate_single_auction_v0_2() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::default();
+    let legacy_code_id = legacy::store_code(&mut app);
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = legacy::instantiate_layout(
+        &mut app,
+        legacy_code_id,
+        &owner,
+        "cw-bidding-platform",
+        "0.2.0",
+        legacy::Layout::SingleV0_2,
+    );
+    contract.migrate(&mut app, &owner, code_id).unwrap();
+
+    let resp = contract.query_config(&app, AUCTION_ID).unwrap();
+    assert_eq!(resp.owner, owner);
+    assert_eq!(resp.bid_denom, BID_DENOM.to_string());
+    assert_eq!(resp.reserve_price, Some(ReservePrice::Public(Uint128::from(100u128))));
+    assert!(resp.end_time.is_some());
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::ReserveNotMet });
+    assert_eq!(contract.query_ownership(&app, AUCTION_ID).unwrap().pending_owner, Some(Addr::unchecked("anna")));
+    assert_eq!(
+        contract.query_total_user_bid(&app, AUCTION_ID, sender1.to_string()).unwrap(),
+        TotalUserBidResp { amount: Uint128::from(90u128) },
+    );
+}
+
+#[test]
+fn migrate_registry_v0_3() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+    let sender2 = Addr::unchecked("anna");
+
+    let mut app = App::default();
+    let legacy_code_id = legacy::store_code(&mut app);
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = legacy::instantiate_layout(
+        &mut app,
+        legacy_code_id,
+        &owner,
+        "cw-bidding-platform",
+        "0.3.0",
+        legacy::Layout::RegistryV0_3,
+    );
+    contract.migrate(&mut app, &owner, code_id).unwrap();
+
+    // Plain bid amounts become indexed bids
+    assert_eq!(
+        contract.query_top_bids(&app, AUCTION_ID, None).unwrap().bids,
+        vec![
+            BidResp { address: sender1.clone(), amount: Uint128::from(90u128) },
+            BidResp { address: sender2.clone(), amount: Uint128::from(50u128) },
+        ],
+    );
+    assert_eq!(
+        contract.query_total_user_bid(&app, AUCTION_ID, sender2.to_string()).unwrap(),
+        TotalUserBidResp { amount: Uint128::from(50u128) },
+    );
+
+    assert_eq!(contract.query_status(&app, AUCTION_ID).unwrap(), StatusResp { status: AuctionStatus::Active });
+    assert_eq!(contract.query_status(&app, 2).unwrap(), StatusResp { status: AuctionStatus::Settled });
+}
+
+#[test]
+fn migrate_registry_v0_4() {
+    let owner = Addr::unchecked("owner");
+    let sender1 = Addr::unchecked("alex");
+
+    let mut app = App::default();
+    let legacy_code_id = legacy::store_code(&mut app);
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = legacy::instantiate_layout(
+        &mut app,
+        legacy_code_id,
+        &owner,
+        "cw-bidding-platform",
+        "0.4.0",
+        legacy::Layout::RegistryV0_4,
+    );
+    contract.migrate(&mut app, &owner, code_id).unwrap();
+
+    // Status flags and settlements become a single status
+    let expected = [
+        AuctionStatus::Cancelled,
+        AuctionStatus::ReserveNotMet,
+        AuctionStatus::AwaitingSettlement,
+        AuctionStatus::Settled,
+        AuctionStatus::Unsold,
+        AuctionStatus::Settled,
+        AuctionStatus::Unsold,
+        AuctionStatus::Active,
+    ];
+    for (auction_id, status) in (1..).zip(expected) {
+        assert_eq!(contract.query_status(&app, auction_id).unwrap(), StatusResp { status });
+    }
+
+    // Bids are left as they were
+    assert_eq!(
+        contract.query_total_user_bid(&app, 1, sender1.to_string()).unwrap(),
+        TotalUserBidResp { amount: Uint128::from(90u128) },
+    );
+    assert_eq!(
+        contract.query_top_bids(&app, 1, None).unwrap().bids,
+        vec![BidResp { address: sender1.clone(), amount: Uint128::from(90u128) }],
+    );
+
+    // Migrated auctions are indexed for listing
+    let resp = contract.query_list_auctions(&app, Some(owner.to_string()), None, None, None).unwrap();
+    assert_eq!(resp.auctions.len(), 8);
+
+    let resp = contract.query_list_auctions(&app, Some(owner.to_string()), Some(false), None, None).unwrap();
+    let open: Vec<_> = resp.auctions.iter().map(|auction| auction.auction_id).collect();
+    assert_eq!(open, vec![8]);
+}
+
+#[test]
+fn migrate_invalid() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = App::default();
+    let legacy_code_id = legacy::store_code(&mut app);
+    let code_id = BiddingContract::store_code(&mut app);
+
+    let contract = legacy::instantiate_contract(&mut app, legacy_code_id, &owner, "cw20-base", "0.1.0");
+    let err = contract.migrate(&mut app, &owner, code_id).unwrap_err();
+    assert_eq!(err, ContractError::InvalidMigrationContract { contract: "cw20-base".to_string() });
+
+    let contract = legacy::instantiate_contract(&mut app, legacy_code_id, &owner, "cw-bidding-platform", "99.0.0");
+    let err = contract.migrate(&mut app, &owner, code_id).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::InvalidMigrationVersion {
+            from: "99.0.0".to_string(),
+            to: env!("CARGO_PKG_VERSION").to_string(),
+        },
+    );
+}
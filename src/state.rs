@@ -1,13 +1,55 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Binary, Decimal, Timestamp, Uint128};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
 use serde::{Deserialize, Serialize};
 
+use crate::msg::{AuctionStatus, AuctionType, BidExtension, CommissionPolicy, FeeBase, MinIncrement, Nft, ReservePrice, SettlementStatus};
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct State {
     pub commodity_uri: String,
+    pub nft: Option<Nft>,
     pub owner: Addr,
     pub bid_comission: Decimal,
-    pub is_closed: bool,
+    pub bid_denom: String,
+    pub cw20_addr: Option<Addr>,
+    pub start_time: Option<Timestamp>,
+    pub end_time: Option<Timestamp>,
+    pub extension: Option<BidExtension>,
+    pub reserve_price: Option<ReservePrice>,
+    pub min_increment: Option<MinIncrement>,
+    pub buy_now_price: Option<Uint128>,
+    #[serde(default)]
+    pub commission_policy: CommissionPolicy,
+    #[serde(default)]
+    pub fee_recipients: Vec<FeeShare>,
+    #[serde(default)]
+    pub fee_base: FeeBase,
+    pub claim_grace_period: Option<u64>,
+    #[serde(default)]
+    pub cancel_requires_no_bids: bool,
+    #[serde(default)]
+    pub auction_type: AuctionType,
+    // Height the auction was created at
+    #[serde(default)]
+    pub start_height: u64,
+    // Open auctions stay `Active`, being scheduled or ended follows from the bid window
+    #[serde(default)]
+    pub status: AuctionStatus,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct FeeShare {
+    pub address: Addr,
+    pub share: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Settlement {
+    pub status: SettlementStatus,
+    pub closed_at: Timestamp,
+    // Amount paid by the winner, when it differs from the winning bid
+    #[serde(default)]
+    pub price: Option<Uint128>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -16,7 +58,161 @@ pub struct Winner {
     pub amount: Uint128,
 }
 
-pub const BID_DENOM: &str = "ATOM";
-pub const STATE: Item<State> = Item::new("state");
-pub const BIDS: Map<&Addr, Uint128> = Map::new("bids");
-pub const WINNER: Item<Winner> = Item::new("winner");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Bid {
+    pub auction_id: u64,
+    pub amount: Uint128,
+    // Commission held by the contract, returned along with the bid
+    #[serde(default)]
+    pub commission: Uint128,
+    // Sealed bid deposit not revealed yet
+    #[serde(default)]
+    pub deposit: Uint128,
+}
+
+impl Bid {
+    pub fn empty(auction_id: u64) -> Self {
+        Bid { auction_id, amount: Uint128::zero(), commission: Uint128::zero(), deposit: Uint128::zero() }
+    }
+
+    // Funds held by the contract for this bid
+    pub fn held(&self) -> Uint128 {
+        self.amount + self.commission + self.deposit
+    }
+}
+
+pub struct BidIndexes<'a> {
+    // Bids of an auction ordered by amount
+    pub amount: MultiIndex<'a, (u64, u128), Bid, (u64, &'a Addr)>,
+}
+
+impl<'a> IndexList<Bid> for BidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<Bid>> + '_> {
+        let v: Vec<&dyn Index<Bid>> = vec![&self.amount];
+        Box::new(v.into_iter())
+    }
+}
+
+// Bid for units of a multi-unit auction, its funds held in `bids`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct UnitBid {
+    pub auction_id: u64,
+    pub quantity: u64,
+    pub unit_price: Uint128,
+    pub seq: u64,
+}
+
+// Unit price followed by the inverted sequence id, so that in descending order
+// the earliest of equal bids comes first
+pub type UnitPriceRank = (u128, u64);
+
+pub struct UnitBidIndexes<'a> {
+    // Bids of an auction by unit price
+    pub price: MultiIndex<'a, (u64, UnitPriceRank), UnitBid, (u64, &'a Addr)>,
+}
+
+impl<'a> IndexList<UnitBid> for UnitBidIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<UnitBid>> + '_> {
+        let v: Vec<&dyn Index<UnitBid>> = vec![&self.price];
+        Box::new(v.into_iter())
+    }
+}
+
+// Single bid as placed, kept even after the total is retracted or paid out
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct BidRecord {
+    pub auction_id: u64,
+    pub bidder: Addr,
+    pub amount: Uint128,
+    pub commission: Uint128,
+    pub net_amount: Uint128,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+pub struct BidRecordIndexes<'a> {
+    // Bids of an auction placed by a bidder
+    pub bidder: MultiIndex<'a, (u64, Addr), BidRecord, (u64, u64)>,
+}
+
+impl<'a> IndexList<BidRecord> for BidRecordIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<BidRecord>> + '_> {
+        let v: Vec<&dyn Index<BidRecord>> = vec![&self.bidder];
+        Box::new(v.into_iter())
+    }
+}
+
+pub const AUCTION_COUNT: Item<u64> = Item::new("auction_count");
+pub const WINNER: Map<u64, Winner> = Map::new("auction_winners");
+pub const SETTLEMENT: Map<u64, Settlement> = Map::new("auction_settlements");
+pub const PENDING_OWNER: Map<u64, Addr> = Map::new("auction_pending_owners");
+pub const BID_SEQ: Map<u64, u64> = Map::new("auction_bid_seq");
+// Sealed bid hashes, removed once revealed
+pub const COMMITMENTS: Map<(u64, &Addr), Binary> = Map::new("auction_commitments");
+// Units allocated to each bidder of a closed multi-unit auction
+pub const ALLOCATIONS: Map<(u64, &Addr), u64> = Map::new("auction_allocations");
+// Bidders who claimed their allocated units
+pub const CLAIMS: Map<(u64, &Addr), bool> = Map::new("auction_claims");
+// Last bidder visited by `RefundAll`
+pub const REFUND_CURSOR: Map<u64, Addr> = Map::new("auction_refund_cursor");
+
+// Auctions indexed for `ListAuctions`, with open as 0 and closed as 1
+pub struct AuctionIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, State, u64>,
+    pub closed: MultiIndex<'a, u8, State, u64>,
+    pub owner_closed: MultiIndex<'a, (Addr, u8), State, u64>,
+}
+
+impl<'a> IndexList<State> for AuctionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<State>> + '_> {
+        let v: Vec<&dyn Index<State>> = vec![&self.owner, &self.closed, &self.owner_closed];
+        Box::new(v.into_iter())
+    }
+}
+
+pub fn auctions<'a>() -> IndexedMap<'a, u64, State, AuctionIndexes<'a>> {
+    let indexes = AuctionIndexes {
+        owner: MultiIndex::new(|_pk, state| state.owner.clone(), "auctions", "auctions__owner"),
+        closed: MultiIndex::new(|_pk, state| state.status.is_closed().into(), "auctions", "auctions__closed"),
+        owner_closed: MultiIndex::new(
+            |_pk, state| (state.owner.clone(), state.status.is_closed().into()),
+            "auctions",
+            "auctions__owner_closed",
+        ),
+    };
+    IndexedMap::new("auctions", indexes)
+}
+
+pub fn bids<'a>() -> IndexedMap<'a, (u64, &'a Addr), Bid, BidIndexes<'a>> {
+    let indexes = BidIndexes {
+        amount: MultiIndex::new(
+            |_pk, bid| (bid.auction_id, bid.amount.u128()),
+            "auction_bids",
+            "auction_bids__amount",
+        ),
+    };
+    IndexedMap::new("auction_bids", indexes)
+}
+
+pub fn unit_bids<'a>() -> IndexedMap<'a, (u64, &'a Addr), UnitBid, UnitBidIndexes<'a>> {
+    let indexes = UnitBidIndexes {
+        price: MultiIndex::new(
+            |_pk, bid| (bid.auction_id, (bid.unit_price.u128(), u64::MAX - bid.seq)),
+            "auction_unit_bids",
+            "auction_unit_bids__price",
+        ),
+    };
+    IndexedMap::new("auction_unit_bids", indexes)
+}
+
+// Append-only log of every bid, keyed by auction id and sequence id
+pub fn bid_log<'a>() -> IndexedMap<'a, (u64, u64), BidRecord, BidRecordIndexes<'a>> {
+    let indexes = BidRecordIndexes {
+        bidder: MultiIndex::new(
+            |_pk, record| (record.auction_id, record.bidder.clone()),
+            "auction_bid_history",
+            "auction_bid_history__bidder",
+        ),
+    };
+    IndexedMap::new("auction_bid_history", indexes)
+}